@@ -1,9 +1,17 @@
 use std::{
     fmt::Pointer,
-    sync::{atomic::AtomicU32, Arc},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
-use nih_plug::{editor::Editor, prelude::Enum};
+use nih_plug::{
+    editor::Editor,
+    nih_error,
+    prelude::{AtomicF32, Enum},
+};
 use nih_plug_vizia::{
     create_vizia_editor,
     vizia::{icons::ICON_CHEVRON_DOWN, prelude::*},
@@ -28,13 +36,19 @@ struct Data {
     text: String,
     switch: bool,
     foo: FooEnum,
-    dark_mode: bool,
+    oversampling_factors: Vec<u32>,
+    oversampling: usize,
+    stepped_slider_style: SliderStyle,
+    levels: Monitor<Vec<ChannelLevel>>,
+    spectrum: RingBufferOutput<f32>,
+    scope: RingBufferOutput<[f32; 2]>,
+    sample_rate: Arc<AtomicF32>,
 }
 
 enum AppEvent {
     ToggleSwitch,
-    ToggleDarkMode,
     SwitchFoo(usize),
+    SetOversampling(usize),
 }
 
 impl Model for Data {
@@ -43,10 +57,8 @@ impl Model for Data {
             AppEvent::ToggleSwitch => {
                 self.switch ^= true;
             }
-            AppEvent::ToggleDarkMode => {
-                self.dark_mode ^= true;
-            }
             AppEvent::SwitchFoo(i) => self.foo = FooEnum::from_index(*i),
+            AppEvent::SetOversampling(i) => self.oversampling = *i,
         });
     }
 }
@@ -54,30 +66,78 @@ impl Model for Data {
 pub(crate) fn create(
     params: Arc<ViewsPluginParams>,
     height: Arc<AtomicU32>,
+    levels: Monitor<Vec<ChannelLevel>>,
+    spectrum: RingBufferOutput<f32>,
+    scope: RingBufferOutput<[f32; 2]>,
+    sample_rate: Arc<AtomicF32>,
 ) -> Option<Box<dyn Editor>> {
     let h = height.clone();
     create_vizia_editor(
         ViziaState::new(move || (800, h.load(std::sync::atomic::Ordering::Relaxed))),
         ViziaTheming::None,
         move |cx, gui| {
-            apply_styles(cx);
+            // Point this at an SCSS file to edit the styles while the editor is open
+            let styles_path = std::env::var_os("ASTRA_STYLES");
+            let styles = match &styles_path {
+                Some(path) => apply_styles_from_file(cx, path),
+                None => apply_styles(cx),
+            };
+            if let Err(err) = styles {
+                nih_error!("Could not apply the styles: {err}");
+            }
+            Theme::default().build(cx);
 
             Data {
                 params: params.clone(),
                 text: "Test".to_owned(),
                 switch: false,
                 foo: Default::default(),
-                dark_mode: false,
+                oversampling_factors: vec![1, 2, 4, 8, 16],
+                oversampling: 0,
+                stepped_slider_style: SliderStyle::CurrentStepLabeled { even: true },
+                levels: levels.clone(),
+                spectrum: spectrum.clone(),
+                scope: scope.clone(),
+                sample_rate: sample_rate.clone(),
             }
             .build(cx);
 
+            let preset_directory =
+                PresetStore::user_directory("Voidstar Audio", "Astra All Views Demo")
+                    .unwrap_or_else(|| std::env::temp_dir().join("astra-presets"));
+            PresetManager::new(
+                gui.clone(),
+                params.as_ref(),
+                PresetStore::new(preset_directory),
+            )
+            .build(cx);
+            UndoHistory::new(gui.clone(), params.as_ref()).build(cx);
+            AbComparison::new(gui, params.as_ref()).build(cx);
+            MidiLearner::new(cx, params.as_ref(), params.midi_learn.clone());
+            HoveredParam::new(params.as_ref(), |id| match id {
+                "gain" => Some("The output level".to_owned()),
+                "shape" => Some("The waveform of the test tone".to_owned()),
+                "cutoff" => Some("The cutoff frequency of the low-pass filter".to_owned()),
+                _ => None,
+            })
+            .build(cx);
+
+            // Used to derive the sliders' ticks from the parameters' ranges
+            let params = params.clone();
+
             VStack::new(cx, |cx| {
                 HStack::new(cx, |cx| {
                     Image::new(cx, "voidstar_logo.png").size(Pixels(16.0));
                     Label::new(cx, "ASTRA - ALL VIEWS").width(Stretch(1.0));
+                    AbCompare::new(cx);
+                    UndoButtons::new(cx);
+                    PresetBrowser::new(cx).width(Pixels(256.0));
                     Label::new(cx, "DARK MODE");
-                    Switch::new(cx, Data::dark_mode)
-                        .on_toggle(|cx| cx.emit(AppEvent::ToggleDarkMode));
+                    Switch::new(
+                        cx,
+                        Theme::color_scheme.map(|color_scheme| *color_scheme == ColorScheme::Dark),
+                    )
+                    .on_toggle(|cx| cx.emit(ThemeEvent::ToggleColorScheme));
                 })
                 .border_width(Pixels(1.0))
                 .space(Pixels(-1.0))
@@ -88,7 +148,8 @@ pub(crate) fn create(
 
                 ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
                     components(cx, "BUTTON", move |cx| {
-                        Button::new(cx, |_| {}, |cx| Label::new(cx, "REGULAR"));
+                        Button::new(cx, |_| {}, |cx| Label::new(cx, "REGULAR"))
+                            .tooltip("Doesn't do anything, but it looks good doing it");
                         Button::new(cx, |_| {}, |cx| Label::new(cx, "PRIMARY")).class("primary");
                         Button::new(cx, |_| {}, |cx| Label::new(cx, "DESTRUCTIVE"))
                             .class("destructive");
@@ -164,6 +225,16 @@ pub(crate) fn create(
                     components(cx, "SELECTOR", |cx| {
                         Selector::new(cx, Data::foo)
                             .on_toggle(|cx, i| cx.emit(AppEvent::SwitchFoo(i)));
+                        Selector::new(cx, Data::foo)
+                            .orientation(SliderOrientation::Vertical)
+                            .on_toggle(|cx, i| cx.emit(AppEvent::SwitchFoo(i)));
+                        Selector::with_items(
+                            cx,
+                            Data::oversampling_factors,
+                            Data::oversampling,
+                            |factor| SelectorItem::new(format!("{factor}X")).disabled(*factor > 8),
+                        )
+                        .on_toggle(|cx, i| cx.emit(AppEvent::SetOversampling(i)));
                     });
 
                     components(cx, "SWITCH", |cx| {
@@ -225,35 +296,145 @@ pub(crate) fn create(
 
                     components(cx, "PARAMETER DROPDOWN", |cx| {
                         ParamDropdown::new(cx, Data::params, |p| &p.shape).width(Pixels(64.0));
+                        ParamDropdown::new(cx, Data::params, |p| &p.note)
+                            .groups((0..11).map(|octave| {
+                                (octave * 12, format!("OCTAVE {}", octave as i32 - 1))
+                            }))
+                            .width(Pixels(64.0));
                     });
 
                     components(cx, "PARAMETER SELECTOR", |cx| {
                         ParamSelector::new(cx, Data::params, |params| &params.shape);
                     });
 
+                    components(cx, "PARAMETER SWITCH", |cx| {
+                        ParamSwitch::new(cx, Data::params, |params| &params.shape);
+                        ParamSwitch::new(cx, Data::params, |params| &params.shape)
+                            .labels(["SIN", "SAW", "SQR"]);
+                        ParamSwitch::new(cx, Data::params, |params| &params.shape).momentary(true);
+                    });
+
                     components(cx, "PARAMETER SLIDER", |cx| {
                         ParamSlider::new(cx, Data::params, |p| &p.gain, None).width(Pixels(160.0));
                         ParamSlider::new(
                             cx,
                             Data::params,
                             |p| &p.gain,
-                            (0..=16).map(|i| {
-                                let pos = i as f32 / 16.0;
-                                let value = (-24.0 + 48.0 * pos) as i32;
-                                let short = value % 2 != 0;
-
-                                SliderTick {
-                                    pos,
-                                    label: (!short).then_some(format!("{value:.2}")),
-                                    short,
-                                }
-                            }),
+                            SliderTick::from_float_param(&params.gain, 4),
                         )
                         .width(Pixels(160.0));
+                        ParamSlider::new(
+                            cx,
+                            Data::params,
+                            |p| &p.cutoff,
+                            SliderTick::from_float_param(&params.cutoff, 3),
+                        )
+                        .width(Pixels(160.0));
+                        ParamSlider::new(cx, Data::params, |p| &p.shape, None)
+                            .slider_style(Data::stepped_slider_style)
+                            .width(Pixels(160.0));
+                    });
+
+                    components(cx, "PARAMETER FADER", |cx| {
+                        ParamSlider::new(
+                            cx,
+                            Data::params,
+                            |p| &p.gain,
+                            SliderTick::from_float_param(&params.gain, 4),
+                        )
+                        .orientation(SliderOrientation::Vertical)
+                        .height(Pixels(160.0));
+                        ParamSlider::new(
+                            cx,
+                            Data::params,
+                            |p| &p.gain,
+                            (0..=8).map(|i| SliderTick {
+                                pos: i as f32 / 8.0,
+                                label: None,
+                                short: i % 2 != 0,
+                            }),
+                        )
+                        .orientation(SliderOrientation::Vertical)
+                        .tick_placement(TickPlacement::Before)
+                        .height(Pixels(160.0));
+                    });
+
+                    components(cx, "PARAMETER KNOB", |cx| {
+                        ParamKnob::new(cx, Data::params, |p| &p.gain, None).width(Pixels(64.0));
+                        ParamKnob::new(
+                            cx,
+                            Data::params,
+                            |p| &p.gain,
+                            (0..=8).map(|i| SliderTick {
+                                pos: i as f32 / 8.0,
+                                label: None,
+                                short: i % 2 != 0,
+                            }),
+                        )
+                        .sweep(300.0)
+                        .width(Pixels(64.0));
+                    });
+
+                    components(cx, "LEVEL METER", |cx| {
+                        LevelMeter::new(
+                            cx,
+                            Data::levels.map(|levels| levels.latest()),
+                            -60.0..=6.0,
+                        )
+                        .width(Pixels(256.0));
+                        LevelMeter::vertical(
+                            cx,
+                            Data::levels.map(|levels| levels.latest()),
+                            -48.0..=0.0,
+                        )
+                        .release(Duration::from_millis(600))
+                        .height(Pixels(128.0));
+                    });
+
+                    components(cx, "SPECTRUM ANALYZER", |cx| {
+                        SpectrumAnalyzer::new(
+                            cx,
+                            Data::spectrum,
+                            Data::sample_rate
+                                .map(|sample_rate| sample_rate.load(Ordering::Relaxed)),
+                            -96.0..=6.0,
+                        )
+                        .peak_hold(true)
+                        .width(Stretch(1.0));
+                    });
+
+                    components(cx, "GENERIC PARAMS VIEW", |cx| {
+                        GenericParamsView::new_custom(cx, Data::params, |id| match id {
+                            "cutoff" => Some(ParamWidget::Knob),
+                            _ => None,
+                        })
+                        .width(Pixels(320.0));
+                    });
+
+                    components(cx, "OSCILLOSCOPE", |cx| {
+                        Oscilloscope::new(
+                            cx,
+                            Data::scope,
+                            Data::sample_rate
+                                .map(|sample_rate| sample_rate.load(Ordering::Relaxed)),
+                        )
+                        .trigger(TriggerMode::RisingEdge)
+                        .time_window(Duration::from_millis(10))
+                        .width(Stretch(1.0));
                     });
                 });
+
+                StatusBar::new(cx).space(Pixels(-1.0));
             })
-            .toggle_class("dark", Data::dark_mode);
+            .color_scheme(Theme::color_scheme);
+
+            ParamContextMenu::with_entries(cx, MidiLearner::menu_entries())
+                .color_scheme(Theme::color_scheme);
+            Tooltip::new(cx).color_scheme(Theme::color_scheme);
+
+            if styles_path.is_some() {
+                StyleErrors::new(cx);
+            }
         },
     )
 }
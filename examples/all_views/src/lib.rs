@@ -1,11 +1,27 @@
 mod editor;
 
+use astra::prelude::{
+    ring_buffer, triple_buffer, CcOverride, ChannelLevel, MidiLearn, Monitor, RingBufferInput,
+    RingBufferOutput, TripleBufferInput,
+};
 use nih_plug::{prelude::*, util::db_to_gain};
 use nih_plug_vizia::ViziaState;
-use std::sync::{atomic::AtomicU32, Arc};
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
 
 pub struct ViewsPlugin {
     params: Arc<ViewsPluginParams>,
+    levels_input: TripleBufferInput<Vec<ChannelLevel>>,
+    levels: Monitor<Vec<ChannelLevel>>,
+    spectrum_input: RingBufferInput<f32>,
+    spectrum: RingBufferOutput<f32>,
+    scope_input: RingBufferInput<[f32; 2]>,
+    scope: RingBufferOutput<[f32; 2]>,
+    sample_rate: Arc<AtomicF32>,
+    /// The gain set by a MIDI CC mapped to the gain parameter, until the parameter itself moves.
+    midi_gain: CcOverride,
 }
 
 #[derive(Enum, PartialEq)]
@@ -21,14 +37,34 @@ struct ViewsPluginParams {
     pub gain: FloatParam,
     #[id = "shape"]
     pub shape: EnumParam<Waveshape>,
+    #[id = "cutoff"]
+    pub cutoff: FloatParam,
+    #[id = "note"]
+    pub note: IntParam,
     #[persist = "editor-height"]
     height: Arc<AtomicU32>,
+    #[persist = "midi-learn"]
+    midi_learn: Arc<MidiLearn>,
 }
 
 impl Default for ViewsPlugin {
     fn default() -> Self {
+        // One level per channel of the stereo layout, so publishing never needs to allocate
+        let (levels_input, levels_output) = triple_buffer(&vec![ChannelLevel::default(); 2]);
+        // Enough for a few frames' worth of samples at high sample rates
+        let (spectrum_input, spectrum) = ring_buffer(16384);
+        let (scope_input, scope) = ring_buffer(16384);
+
         Self {
             params: Arc::new(ViewsPluginParams::default()),
+            levels_input,
+            levels: Monitor::new(levels_output),
+            spectrum_input,
+            spectrum,
+            scope_input,
+            scope,
+            sample_rate: Arc::new(AtomicF32::new(44100.0)),
+            midi_gain: CcOverride::new(),
         }
     }
 }
@@ -48,7 +84,22 @@ impl Default for ViewsPluginParams {
             .with_unit(" dB")
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
             shape: EnumParam::new("Waveshape", Waveshape::Sine),
+            cutoff: FloatParam::new(
+                "Cutoff",
+                1000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            note: IntParam::new("Root Note", 60, IntRange::Linear { min: 0, max: 127 })
+                .with_value_to_string(formatters::v2s_i32_note_formatter())
+                .with_string_to_value(formatters::s2v_i32_note_formatter()),
             height: Arc::new(700.into()),
+            midi_learn: Arc::new(MidiLearn::new()),
         }
     }
 }
@@ -71,7 +122,7 @@ impl Plugin for ViewsPlugin {
         names: PortNames::const_default(),
     }];
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::MidiCCs;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
@@ -86,9 +137,13 @@ impl Plugin for ViewsPlugin {
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
-        _buffer_config: &BufferConfig,
+        buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
+        self.sample_rate
+            .store(buffer_config.sample_rate, Ordering::Relaxed);
+        self.params.midi_learn.register_params(self.params.as_ref());
+
         true
     }
 
@@ -98,21 +153,54 @@ impl Plugin for ViewsPlugin {
         &mut self,
         buffer: &mut Buffer,
         _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        while let Some(event) = context.next_event() {
+            let action = self.params.midi_learn.process_event(&event);
+            self.midi_gain.apply(&self.params.gain, action);
+        }
+        let midi_gain = self.midi_gain.value(&self.params.gain);
+
         for channel_samples in buffer.iter_samples() {
-            let gain = db_to_gain(self.params.gain.smoothed.next());
+            let param_gain = self.params.gain.smoothed.next();
+            let gain = db_to_gain(midi_gain.unwrap_or(param_gain));
 
-            for sample in channel_samples {
+            let mut frame = [0.0; 2];
+            for (sample, scope_sample) in channel_samples.into_iter().zip(&mut frame) {
                 *sample *= gain;
+                *scope_sample = *sample;
             }
+            self.spectrum_input.push((frame[0] + frame[1]) / 2.0);
+            self.scope_input.push(frame);
+        }
+
+        let levels = self.levels_input.input_buffer();
+        for (level, samples) in levels.iter_mut().zip(buffer.as_slice_immutable()) {
+            let peak = samples
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            let mean_square = samples.iter().map(|sample| sample * sample).sum::<f32>()
+                / samples.len().max(1) as f32;
+
+            *level = ChannelLevel {
+                peak,
+                rms: mean_square.sqrt(),
+            };
         }
+        self.levels_input.publish();
 
         ProcessStatus::Normal
     }
 
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
-        editor::create(self.params.clone(), self.params.height.clone())
+        editor::create(
+            self.params.clone(),
+            self.params.height.clone(),
+            self.levels.clone(),
+            self.spectrum.clone(),
+            self.scope.clone(),
+            self.sample_rate.clone(),
+        )
     }
 }
 
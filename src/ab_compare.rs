@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use nih_plug::prelude::{Enum, GuiContext, ParamPtr, Params};
+use nih_plug_vizia::vizia::prelude::*;
+
+use crate::selector::{Selector, SelectorModifiers};
+
+/// One of the two snapshots in an [`AbComparison`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default, Enum)]
+pub enum AbSlot {
+    #[default]
+    A,
+    B,
+}
+
+impl AbSlot {
+    fn other(self) -> Self {
+        match self {
+            AbSlot::A => AbSlot::B,
+            AbSlot::B => AbSlot::A,
+        }
+    }
+}
+
+/// Every parameter's normalized value.
+type Snapshot = Vec<(ParamPtr, f32)>;
+
+/// Holds two snapshots of the plugin's parameters to compare them. The parameters themselves are
+/// always the active slot, so edits go to it. Switching slots stores the parameters in the active
+/// slot and sets them to the other one, with an automation gesture for every parameter that
+/// changes so the host records the switch.
+///
+/// Until a slot has been switched to or copied into for the first time, it starts out with the
+/// parameters as they were at that point.
+///
+/// ```ignore
+/// create_vizia_editor(editor_state, ViziaTheming::None, move |cx, gui| {
+///     AbComparison::new(gui, params.as_ref()).build(cx);
+///
+///     AbCompare::new(cx);
+/// });
+/// ```
+#[derive(Lens)]
+pub struct AbComparison {
+    pub active: AbSlot,
+
+    #[lens(ignore)]
+    gui: Arc<dyn GuiContext>,
+    #[lens(ignore)]
+    params: Vec<ParamPtr>,
+    #[lens(ignore)]
+    slots: [Option<Snapshot>; 2],
+}
+
+pub enum AbEvent {
+    /// Store the parameters in the active slot, and set them to `slot`.
+    Switch(AbSlot),
+    /// Switch to whichever slot isn't active.
+    Toggle,
+    /// Store the parameters in `slot` without switching to it.
+    Capture(AbSlot),
+    /// Replace slot B with slot A. If B is active, the parameters are set to A.
+    CopyAToB,
+}
+
+impl AbComparison {
+    pub fn new(gui: Arc<dyn GuiContext>, params: &dyn Params) -> Self {
+        Self {
+            active: AbSlot::A,
+            gui,
+            params: params
+                .param_map()
+                .into_iter()
+                .map(|(_, param_ptr, _)| param_ptr)
+                .collect(),
+            slots: [None, None],
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        self.params
+            .iter()
+            .map(|&param| (param, unsafe { param.unmodulated_normalized_value() }))
+            .collect()
+    }
+
+    /// Set the parameters to `snapshot`, as one gesture per parameter that changes.
+    fn restore(&self, snapshot: &Snapshot) {
+        for &(param, value) in snapshot {
+            if unsafe { param.unmodulated_normalized_value() } == value {
+                continue;
+            }
+
+            unsafe {
+                self.gui.raw_begin_set_parameter(param);
+                self.gui.raw_set_parameter_normalized(param, value);
+                self.gui.raw_end_set_parameter(param);
+            }
+        }
+    }
+
+    /// The snapshot in `slot`, which for the active slot is the parameters' current state.
+    fn slot(&self, slot: AbSlot) -> Snapshot {
+        match &self.slots[slot.to_index()] {
+            Some(snapshot) if slot != self.active => snapshot.clone(),
+            _ => self.snapshot(),
+        }
+    }
+
+    fn switch(&mut self, slot: AbSlot) {
+        if slot == self.active {
+            return;
+        }
+
+        let current = self.snapshot();
+        if let Some(snapshot) = &self.slots[slot.to_index()] {
+            self.restore(snapshot);
+        }
+
+        self.slots[self.active.to_index()] = Some(current);
+        self.active = slot;
+    }
+
+    fn capture(&mut self, slot: AbSlot) {
+        // The active slot is always up to date
+        if slot != self.active {
+            self.slots[slot.to_index()] = Some(self.snapshot());
+        }
+    }
+
+    fn copy_a_to_b(&mut self) {
+        let a = self.slot(AbSlot::A);
+        if self.active == AbSlot::B {
+            self.restore(&a);
+        }
+
+        self.slots[AbSlot::B.to_index()] = Some(a);
+    }
+}
+
+impl Model for AbComparison {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ab_event, meta| {
+            match ab_event {
+                AbEvent::Switch(slot) => self.switch(*slot),
+                AbEvent::Toggle => self.switch(self.active.other()),
+                AbEvent::Capture(slot) => self.capture(*slot),
+                AbEvent::CopyAToB => self.copy_a_to_b(),
+            }
+
+            meta.consume();
+        });
+    }
+}
+
+/// A [`Selector`] for switching between the slots of an [`AbComparison`], and a button for copying
+/// slot A to slot B.
+pub struct AbCompare;
+
+impl AbCompare {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Selector::new(cx, AbComparison::active)
+                .on_toggle(|cx, i| cx.emit(AbEvent::Switch(AbSlot::from_index(i))));
+            Button::new(
+                cx,
+                |cx| cx.emit(AbEvent::CopyAToB),
+                |cx| Label::new(cx, "COPY A TO B"),
+            )
+            .class("copy");
+        })
+    }
+}
+
+impl View for AbCompare {
+    fn element(&self) -> Option<&'static str> {
+        Some("abcompare")
+    }
+}
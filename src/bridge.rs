@@ -0,0 +1,307 @@
+//! Real-time safe primitives for sending data from a plugin's `process()` function to its editor.
+//!
+//! A [`triple_buffer()`] always holds the most recent complete value, which is useful for peak
+//! levels or spectra that get replaced wholesale every block. A [`ring_buffer()`] instead queues up
+//! individual items, like the samples an oscilloscope needs to draw. Neither of them locks or
+//! allocates on the audio thread.
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossbeam::queue::ArrayQueue;
+use nih_plug_vizia::vizia::prelude::*;
+
+/// How often [`redraw_continuously()`] redraws a view, which is about once per frame at 60 Hz.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The writer's buffer, the reader's buffer, one that may be in transit while the reader swaps
+/// buffers, and one spare. This guarantees the writer can always find a buffer to publish into.
+const TRIPLE_BUFFER_COUNT: usize = 4;
+
+struct TripleBufferShared<T> {
+    /// The most recently published buffer, if the reader hasn't picked it up yet.
+    ready: ArrayQueue<Box<T>>,
+    /// Buffers the reader is done with.
+    free: ArrayQueue<Box<T>>,
+}
+
+/// Create a triple buffer for sending values from the audio thread to the GUI, initialized with
+/// `initial`. The [`TripleBufferInput`] goes to the plugin, the [`TripleBufferOutput`] to the
+/// editor.
+pub fn triple_buffer<T: Clone + Send>(
+    initial: &T,
+) -> (TripleBufferInput<T>, TripleBufferOutput<T>) {
+    let shared = Arc::new(TripleBufferShared {
+        ready: ArrayQueue::new(TRIPLE_BUFFER_COUNT),
+        free: ArrayQueue::new(TRIPLE_BUFFER_COUNT),
+    });
+    for _ in 0..TRIPLE_BUFFER_COUNT - 2 {
+        let _ = shared.free.push(Box::new(initial.clone()));
+    }
+
+    (
+        TripleBufferInput {
+            current: Box::new(initial.clone()),
+            shared: shared.clone(),
+        },
+        TripleBufferOutput {
+            current: Box::new(initial.clone()),
+            shared,
+        },
+    )
+}
+
+/// The audio thread's side of a [`triple_buffer()`].
+pub struct TripleBufferInput<T> {
+    current: Box<T>,
+    shared: Arc<TripleBufferShared<T>>,
+}
+
+impl<T> TripleBufferInput<T> {
+    /// The buffer the next value should be written into. This buffer gets reused, so it contains
+    /// an older value that needs to be overwritten entirely. Writing into the buffer in place
+    /// instead of replacing it avoids allocations for heap allocated types like `Vec`.
+    pub fn input_buffer(&mut self) -> &mut T {
+        &mut self.current
+    }
+
+    /// Make the contents of [`Self::input_buffer()`] available to the GUI. If the GUI hasn't read
+    /// the previously published value yet, then that value is dropped.
+    pub fn publish(&mut self) {
+        // The unread value gets replaced, so its buffer is reused first. That way `ready` never
+        // holds more than the one value the reader should pick up.
+        let Some(next) = self.shared.ready.pop().or_else(|| self.shared.free.pop()) else {
+            return;
+        };
+
+        let published = std::mem::replace(&mut self.current, next);
+        // There's always room since there are only ever `TRIPLE_BUFFER_COUNT` buffers
+        let _ = self.shared.ready.push(published);
+    }
+}
+
+/// The GUI's side of a [`triple_buffer()`].
+pub struct TripleBufferOutput<T> {
+    current: Box<T>,
+    shared: Arc<TripleBufferShared<T>>,
+}
+
+impl<T> TripleBufferOutput<T> {
+    /// Pick up the most recently published value, if there is one. Returns whether the value
+    /// changed.
+    pub fn update(&mut self) -> bool {
+        let mut updated = false;
+        // The writer may publish again between two pops, so this keeps going until it gets to the
+        // newest value
+        while let Some(next) = self.shared.ready.pop() {
+            let previous = std::mem::replace(&mut self.current, next);
+            let _ = self.shared.free.push(previous);
+
+            updated = true;
+        }
+
+        updated
+    }
+
+    /// The most recently published value.
+    pub fn read(&mut self) -> &T {
+        self.update();
+        &self.current
+    }
+
+    /// The value from the last call to [`Self::update()`] or [`Self::read()`].
+    pub fn output_buffer(&self) -> &T {
+        &self.current
+    }
+}
+
+/// Create a bounded single producer, single consumer queue holding up to `capacity` items. The
+/// [`RingBufferInput`] goes to the plugin, the [`RingBufferOutput`] to the editor.
+pub fn ring_buffer<T: Copy + Send>(capacity: usize) -> (RingBufferInput<T>, RingBufferOutput<T>) {
+    let queue = Arc::new(ArrayQueue::new(capacity.max(1)));
+
+    (
+        RingBufferInput {
+            queue: queue.clone(),
+        },
+        RingBufferOutput { queue },
+    )
+}
+
+/// The audio thread's side of a [`ring_buffer()`].
+pub struct RingBufferInput<T> {
+    queue: Arc<ArrayQueue<T>>,
+}
+
+impl<T> RingBufferInput<T> {
+    /// Add an item to the queue. When the GUI can't keep up (or the editor isn't open), the oldest
+    /// item is dropped to make room.
+    pub fn push(&mut self, item: T) {
+        self.queue.force_push(item);
+    }
+}
+
+/// The GUI's side of a [`ring_buffer()`]. This can be cloned to be shared between views, but each
+/// item is only received once.
+pub struct RingBufferOutput<T> {
+    queue: Arc<ArrayQueue<T>>,
+}
+
+impl<T> Clone for RingBufferOutput<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<T> RingBufferOutput<T> {
+    /// Take the oldest item from the queue.
+    pub fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    /// Take all items currently in the queue, oldest first.
+    pub fn drain(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.queue.pop())
+    }
+
+    /// The number of items that can be held before the oldest ones are dropped.
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+impl<T: 'static> Data for RingBufferOutput<T> {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.queue, &other.queue)
+    }
+}
+
+/// A [`TripleBufferOutput`] that can be stored in a vizia model. Views bind to the data through a
+/// lens mapped with [`Monitor::latest()`], which polls the triple buffer whenever the view reads
+/// it. Views that show audio data read it while they're drawn, and redraw themselves every frame
+/// with [`redraw_continuously()`].
+///
+/// ```ignore
+/// Data { levels: Monitor::new(levels_output) }.build(cx);
+///
+/// LevelMeter::new(cx, Data::levels.map(|levels| levels.latest()));
+/// ```
+pub struct Monitor<T> {
+    // Only the GUI thread ever locks this, so it's never contended
+    output: Arc<Mutex<TripleBufferOutput<T>>>,
+}
+
+impl<T> Clone for Monitor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            output: self.output.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Monitor<T> {
+    pub fn new(output: TripleBufferOutput<T>) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+        }
+    }
+
+    /// Poll the triple buffer and return a copy of the most recent value.
+    pub fn latest(&self) -> T {
+        let mut output = self.output.lock().unwrap();
+        output.read().clone()
+    }
+
+    /// Poll the triple buffer and compute something from the most recent value without copying
+    /// it.
+    pub fn map<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let mut output = self.output.lock().unwrap();
+        f(output.read())
+    }
+}
+
+impl<T: 'static> Data for Monitor<T> {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.output, &other.output)
+    }
+}
+
+/// Sent to a view by [`redraw_continuously()`] once per frame.
+pub(crate) struct RedrawEvent;
+
+/// Keep sending [`RedrawEvent`]s to the current view until the editor is closed. Views that read
+/// data from the audio thread while they're drawn handle these with `cx.needs_redraw()`, since
+/// nothing else would redraw them when new data comes in.
+pub(crate) fn redraw_continuously(cx: &mut Context) {
+    cx.spawn(|cx| loop {
+        thread::sleep(REDRAW_INTERVAL);
+
+        // This fails once the editor has been closed
+        if cx.emit(RedrawEvent).is_err() {
+            break;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triple_buffer_reads_initial_value() {
+        let (_input, mut output) = triple_buffer(&1);
+
+        assert!(!output.update());
+        assert_eq!(*output.read(), 1);
+    }
+
+    #[test]
+    fn triple_buffer_reads_latest_value() {
+        let (mut input, mut output) = triple_buffer(&0);
+
+        *input.input_buffer() = 1;
+        input.publish();
+        *input.input_buffer() = 2;
+        input.publish();
+
+        assert!(output.update());
+        assert_eq!(*output.output_buffer(), 2);
+        assert!(!output.update());
+        assert_eq!(*output.output_buffer(), 2);
+    }
+
+    #[test]
+    fn triple_buffer_survives_many_unread_publishes() {
+        let (mut input, mut output) = triple_buffer(&0);
+
+        for i in 1..=TRIPLE_BUFFER_COUNT * 3 {
+            *input.input_buffer() = i;
+            input.publish();
+        }
+        assert_eq!(*output.read(), TRIPLE_BUFFER_COUNT * 3);
+
+        // Buffers are recycled after reading, so publishing keeps working
+        for i in 100..110 {
+            *input.input_buffer() = i;
+            input.publish();
+            assert_eq!(*output.read(), i);
+        }
+    }
+
+    #[test]
+    fn ring_buffer_drops_oldest_items() {
+        let (mut input, output) = ring_buffer(3);
+
+        for i in 0..5 {
+            input.push(i);
+        }
+
+        assert_eq!(output.drain().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(output.pop(), None);
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+use nih_plug_vizia::vizia::image::ImageError;
+
+/// Something that went wrong while setting up ASTRA's fonts, images or styles. None of these
+/// cause a panic, so a plugin can log them and keep running with a partially styled editor.
+#[derive(Debug)]
+pub enum AstraError {
+    /// The data for a font isn't a TrueType or OpenType font.
+    Font { asset: String },
+    /// An image couldn't be decoded.
+    Image { asset: String, source: ImageError },
+    /// An SCSS stylesheet couldn't be compiled.
+    Scss(Box<grass::Error>),
+    /// Vizia couldn't add or parse a stylesheet.
+    Stylesheet(std::io::Error),
+}
+
+impl AstraError {
+    /// The name of the font or image that failed to load, if the error was caused by one.
+    pub fn asset(&self) -> Option<&str> {
+        match self {
+            AstraError::Font { asset } | AstraError::Image { asset, .. } => Some(asset),
+            AstraError::Scss(_) | AstraError::Stylesheet(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for AstraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstraError::Font { asset } => write!(f, "'{asset}' is not a TrueType or OpenType font"),
+            AstraError::Image { asset, source } => {
+                write!(f, "could not decode the image '{asset}': {source}")
+            }
+            AstraError::Scss(err) => write!(f, "could not compile the stylesheet: {err}"),
+            AstraError::Stylesheet(err) => write!(f, "could not apply the stylesheet: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for AstraError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AstraError::Font { .. } => None,
+            AstraError::Image { source, .. } => Some(source),
+            AstraError::Scss(err) => Some(err.as_ref()),
+            AstraError::Stylesheet(err) => Some(err),
+        }
+    }
+}
+
+impl From<Box<grass::Error>> for AstraError {
+    fn from(err: Box<grass::Error>) -> Self {
+        AstraError::Scss(err)
+    }
+}
+
+impl From<std::io::Error> for AstraError {
+    fn from(err: std::io::Error) -> Self {
+        AstraError::Stylesheet(err)
+    }
+}
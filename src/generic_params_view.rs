@@ -0,0 +1,200 @@
+use indexmap::IndexMap;
+use nih_plug::prelude::*;
+use nih_plug_vizia::vizia::prelude::*;
+
+use crate::{
+    param_dropdown::ParamDropdown,
+    param_knob::ParamKnob,
+    param_selector::ParamSelector,
+    param_slider::{ParamSlider, SliderTick},
+    param_switch::ParamSwitch,
+};
+
+/// Enum parameters with up to this many variants get a [`ParamSelector`], larger ones get a
+/// [`ParamDropdown`].
+const SELECTOR_MAX_VARIANTS: usize = 4;
+
+/// The number of labeled ticks on sliders for continuous parameters.
+const SLIDER_MAJOR_TICKS: usize = 4;
+
+/// The widgets [`GenericParamsView`] can use for a parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamWidget {
+    Slider,
+    Knob,
+    Switch,
+    Selector,
+    Dropdown,
+    /// Leave the parameter out entirely.
+    Hidden,
+}
+
+/// An editor generated from a plugin's [`Params`]. Parameters are grouped by the nested params
+/// struct they belong to, parameters with [`ParamFlags::HIDE_IN_GENERIC_UI`] are left out, and
+/// every other parameter gets a widget based on its type:
+///
+/// - [`BoolParam`]s get a [`ParamSwitch`].
+/// - [`EnumParam`]s get a [`ParamSelector`] if they have only a few variants, otherwise a
+///   [`ParamDropdown`].
+/// - [`FloatParam`]s and [`IntParam`]s get a [`ParamSlider`] with ticks generated from their range.
+///
+/// Use [`GenericParamsView::new_custom()`] to pick different widgets for some parameters.
+pub struct GenericParamsView;
+
+impl GenericParamsView {
+    pub fn new<L, PsRef, Ps>(cx: &mut Context, params: L) -> Handle<Self>
+    where
+        L: Lens<Target = PsRef> + Clone,
+        PsRef: AsRef<Ps> + 'static,
+        Ps: Params + ?Sized,
+    {
+        Self::new_custom(cx, params, |_| None)
+    }
+
+    /// The same as [`GenericParamsView::new()`], but `widget_for_id` can return a widget to use
+    /// for the parameter with the given ID instead of the default one. Widgets that need discrete
+    /// steps, like [`ParamWidget::Selector`], are only used for parameters that have them.
+    pub fn new_custom<L, PsRef, Ps>(
+        cx: &mut Context,
+        params: L,
+        widget_for_id: impl Fn(&str) -> Option<ParamWidget>,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = PsRef> + Clone,
+        PsRef: AsRef<Ps> + 'static,
+        Ps: Params + ?Sized,
+    {
+        Self.build(cx, |cx| {
+            let param_map = params
+                .clone()
+                .map(|params| params.as_ref().param_map())
+                .get(cx);
+
+            // Groups are shown in the order their first parameter appears in, even when
+            // `param_map()` doesn't list a group's parameters next to each other
+            let mut groups: IndexMap<String, Vec<(String, ParamPtr)>> = IndexMap::new();
+            for (id, param_ptr, group) in param_map {
+                if unsafe { param_ptr.flags() }.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
+                    continue;
+                }
+
+                groups.entry(group).or_default().push((id, param_ptr));
+            }
+
+            for (group, members) in groups {
+                VStack::new(cx, |cx| {
+                    if !group.is_empty() {
+                        Label::new(cx, group.replace('/', " / ").to_uppercase().as_str())
+                            .class("group-title");
+                    }
+
+                    for (id, param_ptr) in members {
+                        let widget = widget_for_id(&id)
+                            .filter(|widget| supports(*widget, param_ptr))
+                            .unwrap_or_else(|| default_widget(param_ptr));
+
+                        make_widget(cx, params.clone(), param_ptr, widget);
+                    }
+                })
+                .class("group");
+            }
+        })
+    }
+}
+
+impl View for GenericParamsView {
+    fn element(&self) -> Option<&'static str> {
+        Some("genericparamsview")
+    }
+}
+
+fn default_widget(param_ptr: ParamPtr) -> ParamWidget {
+    match param_ptr {
+        ParamPtr::BoolParam(_) => ParamWidget::Switch,
+        ParamPtr::EnumParam(_) => {
+            let variants = unsafe { param_ptr.step_count() }.unwrap_or_default() + 1;
+            if variants <= SELECTOR_MAX_VARIANTS {
+                ParamWidget::Selector
+            } else {
+                ParamWidget::Dropdown
+            }
+        }
+        ParamPtr::FloatParam(_) | ParamPtr::IntParam(_) => ParamWidget::Slider,
+    }
+}
+
+fn supports(widget: ParamWidget, param_ptr: ParamPtr) -> bool {
+    match widget {
+        ParamWidget::Selector | ParamWidget::Dropdown => {
+            unsafe { param_ptr.step_count() }.is_some()
+        }
+        _ => true,
+    }
+}
+
+/// Build `widget` for the parameter behind `param_ptr`.
+fn make_widget<L, PsRef, Ps>(cx: &mut Context, params: L, param_ptr: ParamPtr, widget: ParamWidget)
+where
+    L: Lens<Target = PsRef> + Clone,
+    PsRef: AsRef<Ps> + 'static,
+    Ps: Params + ?Sized,
+{
+    // The pointers in `param_map()` stay valid for as long as the params object is alive, which
+    // the lens keeps it for
+    match param_ptr {
+        ParamPtr::FloatParam(p) => {
+            let ticks = SliderTick::from_float_param(unsafe { &*p }, SLIDER_MAJOR_TICKS);
+            build_widget(cx, params, move |_| unsafe { &*p }, widget, ticks);
+        }
+        ParamPtr::IntParam(p) => {
+            let ticks = SliderTick::from_int_param(unsafe { &*p }, SLIDER_MAJOR_TICKS);
+            build_widget(cx, params, move |_| unsafe { &*p }, widget, ticks);
+        }
+        ParamPtr::BoolParam(p) => build_widget(cx, params, move |_| unsafe { &*p }, widget, None),
+        ParamPtr::EnumParam(p) => build_widget(cx, params, move |_| unsafe { &*p }, widget, None),
+    }
+}
+
+fn build_widget<L, Params, P, FMap>(
+    cx: &mut Context,
+    params: L,
+    params_to_param: FMap,
+    widget: ParamWidget,
+    ticks: impl IntoIterator<Item = SliderTick>,
+) where
+    L: Lens<Target = Params> + Clone,
+    Params: 'static,
+    P: Param + 'static,
+    FMap: Fn(&Params) -> &P + Copy + 'static,
+{
+    // Sliders and knobs show the parameter's name themselves, the other widgets need a label
+    let labeled = |cx: &mut Context, content: &dyn Fn(&mut Context)| {
+        let name = params
+            .clone()
+            .map(move |params| params_to_param(params).name().to_uppercase());
+        HStack::new(cx, |cx| {
+            Label::new(cx, name).class("name");
+            content(cx);
+        })
+        .class("labeled");
+    };
+
+    match widget {
+        ParamWidget::Slider => {
+            ParamSlider::new(cx, params.clone(), params_to_param, ticks);
+        }
+        ParamWidget::Knob => {
+            ParamKnob::new(cx, params.clone(), params_to_param, ticks);
+        }
+        ParamWidget::Switch => labeled(cx, &|cx| {
+            ParamSwitch::new(cx, params.clone(), params_to_param);
+        }),
+        ParamWidget::Selector => labeled(cx, &|cx| {
+            ParamSelector::new(cx, params.clone(), params_to_param);
+        }),
+        ParamWidget::Dropdown => labeled(cx, &|cx| {
+            ParamDropdown::new(cx, params.clone(), params_to_param);
+        }),
+        ParamWidget::Hidden => {}
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    cell::RefCell,
+    ops::RangeInclusive,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use nih_plug::util;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+use crate::{
+    bridge::{redraw_continuously, RedrawEvent},
+    orientation::Orientation,
+    param_slider::{tick_marks, SliderTick},
+};
+
+/// The level of a single channel as linear gain, usually computed once per block in the plugin's
+/// `process()` function. Meters that only need one of the two can set both to the same value.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Data)]
+pub struct ChannelLevel {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// A peak and RMS meter for one or more channels, with peak hold and a clip indicator that can be
+/// reset by clicking on it.
+#[derive(Lens)]
+pub struct LevelMeter {
+    attack_ms: f32,
+    release_ms: f32,
+    hold_ms: f32,
+}
+
+const DEFAULT_ATTACK_MS: f32 = 5.0;
+const DEFAULT_RELEASE_MS: f32 = 300.0;
+const DEFAULT_HOLD_MS: f32 = 1000.0;
+
+/// The ballistics state shared between the meter's layers. This is updated by the track layer,
+/// which is drawn first, on every frame the meter redraws itself.
+#[derive(Default)]
+struct MeterState {
+    channels: Vec<ChannelState>,
+    clipped: bool,
+    last_update: Option<Instant>,
+}
+
+#[derive(Clone, Copy)]
+struct ChannelState {
+    peak_db: f32,
+    rms_db: f32,
+    hold_db: f32,
+    hold_since: Instant,
+}
+
+impl LevelMeter {
+    /// Create a meter for the channel levels in `levels`, displaying `db_range` decibels.
+    pub fn new<L>(cx: &mut Context, levels: L, db_range: RangeInclusive<f32>) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<ChannelLevel>> + Clone,
+    {
+        Self::with_orientation(cx, levels, db_range, Orientation::Horizontal)
+    }
+
+    /// The same as [`LevelMeter::new()`], but laid out vertically with the bars filling from the
+    /// bottom up.
+    pub fn vertical<L>(cx: &mut Context, levels: L, db_range: RangeInclusive<f32>) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<ChannelLevel>> + Clone,
+    {
+        Self::with_orientation(cx, levels, db_range, Orientation::Vertical).class("vertical")
+    }
+
+    fn with_orientation<L>(
+        cx: &mut Context,
+        levels: L,
+        db_range: RangeInclusive<f32>,
+        orientation: Orientation,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<ChannelLevel>> + Clone,
+    {
+        let vertical = orientation == Orientation::Vertical;
+        let (min_db, max_db) = (*db_range.start(), *db_range.end());
+        let state = Rc::new(RefCell::new(MeterState::default()));
+
+        Self {
+            attack_ms: DEFAULT_ATTACK_MS,
+            release_ms: DEFAULT_RELEASE_MS,
+            hold_ms: DEFAULT_HOLD_MS,
+        }
+        .build(cx, |cx| {
+            // The ballistics keep moving after the levels stop changing, so the meter redraws
+            // every frame instead of when the levels change
+            redraw_continuously(cx);
+
+            let clip_indicator = |cx: &mut Context| {
+                ClipIndicator {
+                    state: state.clone(),
+                }
+                .build(cx, |_| {})
+                .class("clip");
+            };
+            let bars = |cx: &mut Context| {
+                ZStack::new(cx, |cx| {
+                    let layer = |kind| MeterLayer {
+                        kind,
+                        levels: levels.clone(),
+                        state: state.clone(),
+                        min_db,
+                        max_db,
+                        vertical,
+                    };
+
+                    layer(MeterLayerKind::Track)
+                        .build(cx, |_| {})
+                        .class("track");
+                    layer(MeterLayerKind::Peak)
+                        .build(cx, |_| {})
+                        .class("peak")
+                        .hoverable(false);
+                    layer(MeterLayerKind::Rms)
+                        .build(cx, |_| {})
+                        .class("rms")
+                        .hoverable(false);
+                    layer(MeterLayerKind::Hold)
+                        .build(cx, |_| {})
+                        .class("hold")
+                        .hoverable(false);
+                })
+                .class("bars");
+            };
+
+            // The clip indicator sits at the loud end of the meter, outside of the area the ticks
+            // are aligned to
+            let ticks = db_ticks(min_db, max_db);
+            if vertical {
+                VStack::new(cx, |cx| {
+                    clip_indicator(cx);
+                    HStack::new(cx, |cx| {
+                        bars(cx);
+                        tick_marks(cx, &ticks, true, false);
+                    })
+                    .class("body");
+                });
+            } else {
+                HStack::new(cx, |cx| {
+                    VStack::new(cx, |cx| {
+                        bars(cx);
+                        tick_marks(cx, &ticks, false, false);
+                    })
+                    .class("body");
+                    clip_indicator(cx);
+                });
+            }
+        })
+    }
+}
+
+pub trait LevelMeterModifiers {
+    /// How quickly the bars rise, as the time constant of the smoothing. Defaults to 5 ms.
+    fn attack(self, attack: Duration) -> Self;
+
+    /// How quickly the bars fall, as the time constant of the smoothing. Defaults to 300 ms.
+    fn release(self, release: Duration) -> Self;
+
+    /// How long the peak hold line stays in place before following the peak again. Defaults to one
+    /// second.
+    fn hold(self, hold: Duration) -> Self;
+}
+
+impl LevelMeterModifiers for Handle<'_, LevelMeter> {
+    fn attack(self, attack: Duration) -> Self {
+        self.modify(|meter| meter.attack_ms = attack.as_secs_f32() * 1000.0)
+    }
+
+    fn release(self, release: Duration) -> Self {
+        self.modify(|meter| meter.release_ms = release.as_secs_f32() * 1000.0)
+    }
+
+    fn hold(self, hold: Duration) -> Self {
+        self.modify(|meter| meter.hold_ms = hold.as_secs_f32() * 1000.0)
+    }
+}
+
+impl View for LevelMeter {
+    fn element(&self) -> Option<&'static str> {
+        Some("levelmeter")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|RedrawEvent, meta| {
+            cx.needs_redraw();
+            meta.consume();
+        });
+    }
+}
+
+/// Labeled ticks every so many decibels so there end up being roughly five labels.
+fn db_ticks(min_db: f32, max_db: f32) -> Vec<SliderTick> {
+    let span = max_db - min_db;
+    if span <= 0.0 {
+        return Vec::new();
+    }
+
+    let step = [1.0, 3.0, 6.0, 12.0, 24.0, 48.0]
+        .into_iter()
+        .find(|step| span / step <= 6.0)
+        .unwrap_or(96.0);
+    let minor_step = step / 2.0;
+
+    let first = (min_db / minor_step).ceil() as i32;
+    let last = (max_db / minor_step).floor() as i32;
+    (first..=last)
+        .map(|i| {
+            let db = i as f32 * minor_step;
+            let major = i % 2 == 0;
+
+            SliderTick {
+                pos: (db - min_db) / span,
+                label: major.then(|| format!("{db:.0}")),
+                short: !major,
+            }
+        })
+        .collect()
+}
+
+/// The smoothing coefficient for a one pole filter with a time constant of `time_ms`, running once
+/// every `dt` seconds.
+fn smoothing_coefficient(time_ms: f32, dt: f32) -> f32 {
+    if time_ms <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-dt / (time_ms / 1000.0)).exp()
+    }
+}
+
+enum MeterLayerKind {
+    /// The background for all bars, drawn with the `background-color`. This layer also advances
+    /// the ballistics.
+    Track,
+    /// The peak level, drawn with the `color`.
+    Peak,
+    /// The RMS level on top of the peak level, drawn with the `color`.
+    Rms,
+    /// A line at the held peak level, drawn with the `color`.
+    Hold,
+}
+
+struct MeterLayer<L> {
+    kind: MeterLayerKind,
+    levels: L,
+    state: Rc<RefCell<MeterState>>,
+    min_db: f32,
+    max_db: f32,
+    vertical: bool,
+}
+
+impl<L> MeterLayer<L>
+where
+    L: Lens<Target = Vec<ChannelLevel>>,
+{
+    /// Apply the attack and release smoothing to the current levels, and latch the clip indicator.
+    fn update(&self, cx: &mut DrawContext) {
+        let levels = self.levels.get(cx);
+        let attack_ms = LevelMeter::attack_ms.get(cx);
+        let release_ms = LevelMeter::release_ms.get(cx);
+        let hold = Duration::from_secs_f32(LevelMeter::hold_ms.get(cx).max(0.0) / 1000.0);
+
+        let now = Instant::now();
+        let floor_db = self.min_db - 1.0;
+        let mut state = self.state.borrow_mut();
+        let dt = state
+            .last_update
+            .map(|last_update| (now - last_update).as_secs_f32())
+            .unwrap_or(0.0);
+        state.last_update = Some(now);
+
+        state.channels.resize(
+            levels.len(),
+            ChannelState {
+                peak_db: floor_db,
+                rms_db: floor_db,
+                hold_db: floor_db,
+                hold_since: now,
+            },
+        );
+
+        let smooth = |current: f32, target: f32| {
+            let time_ms = if target > current {
+                attack_ms
+            } else {
+                release_ms
+            };
+            current + (target - current) * smoothing_coefficient(time_ms, dt)
+        };
+
+        for (channel, level) in state.channels.iter_mut().zip(levels.iter()) {
+            let peak_db = util::gain_to_db(level.peak.abs()).max(floor_db);
+            let rms_db = util::gain_to_db(level.rms.abs()).max(floor_db);
+
+            channel.peak_db = smooth(channel.peak_db, peak_db);
+            channel.rms_db = smooth(channel.rms_db, rms_db);
+            if channel.peak_db >= channel.hold_db || now - channel.hold_since >= hold {
+                channel.hold_db = channel.peak_db;
+                channel.hold_since = now;
+            }
+        }
+
+        if levels.iter().any(|level| level.peak.abs() >= 1.0) {
+            state.clipped = true;
+        }
+    }
+
+    fn db_to_t(&self, db: f32) -> f32 {
+        ((db - self.min_db) / (self.max_db - self.min_db)).clamp(0.0, 1.0)
+    }
+}
+
+impl<L> View for MeterLayer<L>
+where
+    L: Lens<Target = Vec<ChannelLevel>>,
+{
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        if matches!(self.kind, MeterLayerKind::Track) {
+            self.update(cx);
+        }
+
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let state = self.state.borrow();
+        let channel_count = state.channels.len().max(1);
+        let gap = cx.scale_factor();
+        let (length, breadth) = if self.vertical {
+            (bounds.h, bounds.w)
+        } else {
+            (bounds.w, bounds.h)
+        };
+        let channel_breadth = (breadth - gap * (channel_count - 1) as f32) / channel_count as f32;
+
+        // Every channel gets a slice of the layer, from `start_t` to `end_t` along the meter
+        let mut path = vg::Path::new();
+        let mut add_segment = |channel: usize, start_t: f32, end_t: f32| {
+            let offset = channel as f32 * (channel_breadth + gap);
+            if self.vertical {
+                path.rect(
+                    bounds.x + offset,
+                    bounds.y + bounds.h - end_t * length,
+                    channel_breadth,
+                    (end_t - start_t) * length,
+                );
+            } else {
+                path.rect(
+                    bounds.x + start_t * length,
+                    bounds.y + offset,
+                    (end_t - start_t) * length,
+                    channel_breadth,
+                );
+            }
+        };
+
+        let color = match self.kind {
+            MeterLayerKind::Track => {
+                for channel in 0..channel_count {
+                    add_segment(channel, 0.0, 1.0);
+                }
+
+                cx.background_color()
+            }
+            MeterLayerKind::Peak => {
+                for (channel, channel_state) in state.channels.iter().enumerate() {
+                    add_segment(channel, 0.0, self.db_to_t(channel_state.peak_db));
+                }
+
+                cx.font_color()
+            }
+            MeterLayerKind::Rms => {
+                for (channel, channel_state) in state.channels.iter().enumerate() {
+                    add_segment(channel, 0.0, self.db_to_t(channel_state.rms_db));
+                }
+
+                cx.font_color()
+            }
+            MeterLayerKind::Hold => {
+                let line_width = cx.scale_factor() / length;
+                for (channel, channel_state) in state.channels.iter().enumerate() {
+                    let t = self.db_to_t(channel_state.hold_db);
+                    if t > 0.0 {
+                        add_segment(channel, (t - line_width).max(0.0), t);
+                    }
+                }
+
+                cx.font_color()
+            }
+        };
+
+        canvas.fill_path(&path, &vg::Paint::color(color.into()));
+    }
+}
+
+/// Lights up with the `color` once any channel reached 0 dBFS, until it gets clicked.
+struct ClipIndicator {
+    state: Rc<RefCell<MeterState>>,
+}
+
+impl View for ClipIndicator {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| {
+            if let WindowEvent::MouseDown(MouseButton::Left) = window_event {
+                self.state.borrow_mut().clipped = false;
+                cx.needs_redraw();
+
+                meta.consume();
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let color = if self.state.borrow().clipped {
+            cx.font_color()
+        } else {
+            cx.background_color()
+        };
+
+        let mut path = vg::Path::new();
+        path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&path, &vg::Paint::color(color.into()));
+    }
+}
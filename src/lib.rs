@@ -1,40 +1,138 @@
+pub mod ab_compare;
+pub mod bridge;
+pub mod error;
+pub mod generic_params_view;
+pub mod level_meter;
+pub mod midi_learn;
+pub mod orientation;
+pub mod oscilloscope;
 pub mod param_dropdown;
+pub mod param_knob;
+pub mod param_menu;
 pub mod param_selector;
 pub mod param_slider;
 pub mod param_switch;
+pub mod param_value;
+pub mod preset_browser;
+pub mod presets;
 pub mod selector;
+pub mod spectrum_analyzer;
+pub mod status_bar;
+pub mod style_watcher;
 pub mod tag;
+pub mod theme;
+pub mod ticks;
+pub mod tooltip;
+pub mod undo;
 
+use std::path::Path;
+
+use error::AstraError;
 use nih_plug_vizia::vizia::{image, prelude::*};
 
 pub mod prelude {
     pub use crate::{
-        apply_styles, basics::*, param_dropdown::*, param_selector::*, param_slider::*,
-        param_switch::*, selector::*, tag::*,
+        ab_compare::*, apply_styles, apply_styles_from_file, apply_themed_styles, basics::*,
+        bridge::*, error::*, generic_params_view::*, level_meter::*, midi_learn::*, orientation::*,
+        oscilloscope::*, param_dropdown::*, param_knob::*, param_menu::*, param_selector::*,
+        param_slider::*, param_switch::*, preset_browser::*, presets::*, selector::*,
+        spectrum_analyzer::*, status_bar::*, style_watcher::*, tag::*, theme::*, tooltip::*,
+        undo::*,
     };
 }
 
-pub fn apply_styles(cx: &mut Context) -> Result<(), std::io::Error> {
-    cx.add_font_mem(include_bytes!("../static/VoidstarMono-Regular.otf"));
-    cx.load_image(
+/// Load ASTRA's fonts and images, and add its default stylesheet. The stylesheet is added even if
+/// one of the assets failed to load, in which case the first error is returned.
+pub fn apply_styles(cx: &mut Context) -> Result<(), AstraError> {
+    let assets = load_assets(cx);
+    cx.add_stylesheet(grass::include!("static/styles.scss"))?;
+
+    assets
+}
+
+/// The same as [`apply_styles()`], but with a stylesheet that's generated from ASTRA's styles with
+/// the plugin's own [`ThemeTokens`][theme::ThemeTokens].
+pub fn apply_themed_styles(
+    cx: &mut Context,
+    tokens: &theme::ThemeTokens,
+) -> Result<(), AstraError> {
+    let assets = load_assets(cx);
+    let stylesheet = tokens.stylesheet()?;
+    cx.add_stylesheet(CSS::from_string(&stylesheet))?;
+
+    assets
+}
+
+/// Load ASTRA's fonts and images, and add a stylesheet compiled from the SCSS file at `path`. The
+/// file is recompiled and re-applied whenever it changes, which makes it possible to iterate on a
+/// plugin's look without restarting it. Compile errors are shown by [`StyleErrors`] views instead
+/// of being returned, so a typo doesn't take down the editor.
+///
+/// The file can build on ASTRA's own styles, and import other files from its directory:
+///
+/// ```scss
+/// @use "theme" with ($component-size: 16px);
+/// @use "styles";
+///
+/// button {
+///     border-width: 2px;
+/// }
+/// ```
+///
+/// [`StyleErrors`]: style_watcher::StyleErrors
+pub fn apply_styles_from_file(cx: &mut Context, path: impl AsRef<Path>) -> Result<(), AstraError> {
+    let assets = load_assets(cx);
+    style_watcher::StyleWatcher::watch(cx, path.as_ref())?;
+
+    assets
+}
+
+/// Register the fonts and images the styles refer to. Stops at the first asset that fails to load.
+fn load_assets(cx: &mut Context) -> Result<(), AstraError> {
+    add_font(
+        cx,
+        "VoidstarMono-Regular.otf",
+        include_bytes!("../static/VoidstarMono-Regular.otf"),
+    )?;
+    load_png(
+        cx,
         "voidstar_logo.png",
-        image::load_from_memory_with_format(
-            include_bytes!("../static/voidstar_logo.png"),
-            image::ImageFormat::Png,
-        )
-        .unwrap(),
-        ImageRetentionPolicy::DropWhenUnusedForOneFrame,
-    );
-    cx.load_image(
+        include_bytes!("../static/voidstar_logo.png"),
+    )?;
+    load_png(
+        cx,
         "chevron_down.png",
-        image::load_from_memory_with_format(
-            include_bytes!("../static/chevron_down.png"),
-            image::ImageFormat::Png,
-        )
-        .unwrap(),
-        ImageRetentionPolicy::DropWhenUnusedForOneFrame,
-    );
-    cx.add_stylesheet(grass::include!("static/styles.scss"))
+        include_bytes!("../static/chevron_down.png"),
+    )?;
+
+    Ok(())
+}
+
+fn add_font(cx: &mut Context, name: &str, data: &'static [u8]) -> Result<(), AstraError> {
+    // Vizia only parses fonts once they're used, so at least make sure this is a TrueType
+    // (0x00010000 or 'true') or OpenType ('OTTO') font before registering it
+    match data.get(..4) {
+        Some([0, 1, 0, 0] | b"true" | b"OTTO") => {
+            cx.add_font_mem(data);
+            Ok(())
+        }
+        _ => Err(AstraError::Font {
+            asset: name.to_owned(),
+        }),
+    }
+}
+
+fn load_png(cx: &mut Context, name: &str, data: &[u8]) -> Result<(), AstraError> {
+    let image =
+        image::load_from_memory_with_format(data, image::ImageFormat::Png).map_err(|source| {
+            AstraError::Image {
+                asset: name.to_owned(),
+                source,
+            }
+        })?;
+    cx.load_image(name, image, ImageRetentionPolicy::DropWhenUnusedForOneFrame);
+
+    Ok(())
 }
 
 pub mod basics {
@@ -0,0 +1,548 @@
+//! Binding MIDI CCs to parameters by moving a controller while a parameter is waiting for one.
+//!
+//! The plugin keeps a [`MidiLearn`] in its params struct, so the mappings are saved with its
+//! state, registers its parameters with [`MidiLearn::register_params()`], and passes its note
+//! events to [`MidiLearn::process_event()`]. That returns the values of mapped CCs, which the
+//! plugin applies itself, whether the editor is open or not, for instance with a [`CcOverride`]
+//! per parameter. The values don't reach the parameters, so the host and the editor keep showing
+//! the parameters' own values until they're changed. The editor builds a [`MidiLearner`], which
+//! binds incoming CCs to the parameter that's learning and shows which parameters are mapped.
+//!
+//! ```ignore
+//! #[derive(Params)]
+//! struct MyParams {
+//!     #[persist = "midi-learn"]
+//!     midi_learn: Arc<MidiLearn>,
+//! }
+//!
+//! // In `initialize()`
+//! self.params.midi_learn.register_params(self.params.as_ref());
+//!
+//! // In `process()`
+//! while let Some(event) = context.next_event() {
+//!     let action = self.params.midi_learn.process_event(&event);
+//!     self.cutoff_cc.apply(&self.params.cutoff, action);
+//! }
+//! let cutoff = self.cutoff_cc.value(&self.params.cutoff).unwrap_or(self.params.cutoff.value());
+//!
+//! // In the editor
+//! MidiLearner::new(cx, params.as_ref(), params.midi_learn.clone());
+//! ParamContextMenu::with_entries(cx, MidiLearner::menu_entries());
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossbeam::queue::ArrayQueue;
+use nih_plug::{
+    params::persist::PersistentField,
+    prelude::{NoteEvent, Param, ParamPtr, Params, SysExMessage},
+};
+use nih_plug_vizia::{vizia::prelude::*, widgets::RawParamEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::{param_menu::ParamMenuEntry, param_value::same_step};
+
+const MIDI_CHANNELS: usize = 16;
+const MIDI_CCS: usize = 128;
+
+/// The most CC messages that are kept until the editor picks them up.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How often the editor binds the CCs in the queue. It also checks while no parameter is learning,
+/// so CCs that arrived just before learning was cancelled are dropped instead of bound later.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The entry in [`MidiLearn`]'s `targets` for a channel and CC pair that isn't mapped.
+const UNMAPPED: usize = usize::MAX;
+
+/// A MIDI CC bound to a parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    /// From 0 through 15.
+    pub channel: u8,
+    pub cc: u8,
+    pub param_id: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MidiCc {
+    channel: u8,
+    cc: u8,
+    value: f32,
+}
+
+/// What [`MidiLearn::process_event()`] did with a note event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiCcAction {
+    /// The event isn't a CC, or the CC isn't mapped to a parameter.
+    None,
+    /// The CC was sent to the editor to be bound to the parameter that's learning.
+    Learned,
+    /// The CC is mapped to this parameter, and moved it to this normalized value. The plugin can
+    /// apply the value directly or smooth it, or keep it in a [`CcOverride`].
+    Set(ParamPtr, f32),
+}
+
+/// A parameter's value as last set by a mapped CC, which the plugin uses in place of the
+/// parameter's own value. nih-plug doesn't let a plugin change its parameters from `process()`, so
+/// CCs never reach the parameter itself: the host, the parameter's smoother, and the editor's
+/// widgets keep showing the value the parameter had. The CC's value is used until the parameter
+/// changes on its own, for instance through automation or the editor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CcOverride {
+    /// The CC's normalized value, and the parameter's unmodulated normalized value when it
+    /// arrived.
+    values: Option<(f32, f32)>,
+}
+
+impl CcOverride {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the value from a [`MidiCcAction::Set`] for `param`. Returns whether the action was for
+    /// this parameter.
+    pub fn apply<P: Param>(&mut self, param: &P, action: MidiCcAction) -> bool {
+        match action {
+            MidiCcAction::Set(target, normalized_value) if target == param.as_ptr() => {
+                self.values = Some((normalized_value, param.unmodulated_normalized_value()));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The plain value the last CC set, or `None` if the parameter has changed since then or no CC
+    /// has arrived.
+    pub fn value<P: Param>(&mut self, param: &P) -> Option<P::Plain> {
+        let (cc_value, param_value) = self.values?;
+        if !same_step(param, param.unmodulated_normalized_value(), param_value) {
+            self.values = None;
+            return None;
+        }
+
+        Some(param.preview_plain(cc_value))
+    }
+}
+
+/// The MIDI mappings, shared between the plugin and its editor. This is a
+/// [`PersistentField`], so it can be stored in the plugin's params struct with `#[persist]`.
+pub struct MidiLearn {
+    mappings: RwLock<Vec<MidiMapping>>,
+    /// The plugin's parameters, from [`MidiLearn::register_params()`].
+    params: OnceLock<Vec<(String, ParamPtr)>>,
+    /// The index in `params` of the parameter each channel and CC pair is mapped to, indexed by
+    /// `channel * 128 + cc`, or [`UNMAPPED`]. This lets [`MidiLearn::process_event()`] look up
+    /// mapped CCs without locking.
+    targets: Vec<AtomicUsize>,
+    /// Whether the editor is waiting for a CC to bind.
+    learning: AtomicBool,
+    /// CCs that are waiting for the editor to bind them.
+    incoming: ArrayQueue<MidiCc>,
+}
+
+impl Default for MidiLearn {
+    fn default() -> Self {
+        Self {
+            mappings: RwLock::new(Vec::new()),
+            params: OnceLock::new(),
+            targets: (0..MIDI_CHANNELS * MIDI_CCS)
+                .map(|_| AtomicUsize::new(UNMAPPED))
+                .collect(),
+            learning: AtomicBool::new(false),
+            incoming: ArrayQueue::new(QUEUE_CAPACITY),
+        }
+    }
+}
+
+impl MidiLearn {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tell the mappings which parameters their IDs belong to. The plugin calls this once, in its
+    /// `initialize()` function, before passing events to [`MidiLearn::process_event()`].
+    pub fn register_params(&self, params: &dyn Params) {
+        let param_map = params
+            .param_map()
+            .into_iter()
+            .map(|(id, param_ptr, _)| (id, param_ptr))
+            .collect();
+        if self.params.set(param_map).is_ok() {
+            self.update_targets(&self.mappings.read().unwrap());
+        }
+    }
+
+    /// Look up the parameter a CC from the plugin's `process()` function is mapped to, or pass the
+    /// CC to the editor while a parameter is learning. Mapped CCs only take effect when the plugin
+    /// applies the values this returns, and they don't go through the host's automation. This
+    /// neither locks nor allocates.
+    pub fn process_event<S: SysExMessage>(&self, event: &NoteEvent<S>) -> MidiCcAction {
+        let &NoteEvent::MidiCC {
+            channel, cc, value, ..
+        } = event
+        else {
+            return MidiCcAction::None;
+        };
+
+        if self.learning.load(Ordering::Relaxed) {
+            self.incoming.force_push(MidiCc { channel, cc, value });
+            return MidiCcAction::Learned;
+        }
+
+        let target = self
+            .targets
+            .get(channel as usize * MIDI_CCS + cc as usize)
+            .map_or(UNMAPPED, |target| target.load(Ordering::Relaxed));
+        match self.params.get().and_then(|params| params.get(target)) {
+            Some((_, param)) => MidiCcAction::Set(*param, value),
+            None => MidiCcAction::None,
+        }
+    }
+
+    /// A copy of the current mappings.
+    pub fn mappings(&self) -> Vec<MidiMapping> {
+        self.mappings.read().unwrap().clone()
+    }
+
+    /// Replace every mapping.
+    pub fn set_mappings(&self, mappings: Vec<MidiMapping>) {
+        self.update_targets(&mappings);
+        *self.mappings.write().unwrap() = mappings;
+    }
+
+    /// Point every channel and CC pair at the parameter it's mapped to. Mappings for parameters
+    /// that haven't been registered yet are picked up by [`MidiLearn::register_params()`].
+    fn update_targets(&self, mappings: &[MidiMapping]) {
+        let Some(params) = self.params.get() else {
+            return;
+        };
+
+        // The audio thread may be reading the targets, so the ones that keep their mapping are
+        // never blanked in between
+        let mut new_targets = vec![UNMAPPED; self.targets.len()];
+        for mapping in mappings {
+            let index = params.iter().position(|(id, _)| *id == mapping.param_id);
+            let target =
+                new_targets.get_mut(mapping.channel as usize * MIDI_CCS + mapping.cc as usize);
+            if let (Some(index), Some(target)) = (index, target) {
+                *target = index;
+            }
+        }
+
+        for (target, new_target) in self.targets.iter().zip(new_targets) {
+            if target.load(Ordering::Relaxed) != new_target {
+                target.store(new_target, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Stop sending CCs to the editor and drop the ones it hasn't bound yet.
+    fn stop_learning(&self) {
+        self.learning.store(false, Ordering::Relaxed);
+        while self.incoming.pop().is_some() {}
+    }
+
+    /// Bind a CC to a parameter, replacing the CC's and the parameter's previous mappings.
+    pub fn bind(&self, channel: u8, cc: u8, param_id: &str) {
+        let mut mappings = self.mappings();
+        mappings.retain(|mapping| {
+            (mapping.channel, mapping.cc) != (channel, cc) && mapping.param_id != param_id
+        });
+        mappings.push(MidiMapping {
+            channel,
+            cc,
+            param_id: param_id.to_owned(),
+        });
+
+        self.set_mappings(mappings);
+    }
+
+    /// Remove a parameter's mapping.
+    pub fn unbind(&self, param_id: &str) {
+        let mut mappings = self.mappings();
+        mappings.retain(|mapping| mapping.param_id != param_id);
+
+        self.set_mappings(mappings);
+    }
+}
+
+impl<'a> PersistentField<'a, Vec<MidiMapping>> for MidiLearn {
+    fn set(&self, new_value: Vec<MidiMapping>) {
+        self.set_mappings(new_value);
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&Vec<MidiMapping>) -> R,
+    {
+        f(&self.mappings.read().unwrap())
+    }
+}
+
+/// The editor's side of [`MidiLearn`]. While a parameter is learning, the next CC that comes in is
+/// bound to it. Param widgets get the `learning` class while they're waiting for a CC, and the
+/// `mapped` class while they have one. Applying mapped CCs is up to the plugin.
+///
+/// Learning starts with a [`MidiLearnEvent`], by alt-clicking a param widget, or from the
+/// [`ParamContextMenu`][crate::param_menu::ParamContextMenu] with the entries from
+/// [`MidiLearner::menu_entries()`].
+pub struct MidiLearner {
+    midi_learn: Arc<MidiLearn>,
+    params_by_id: HashMap<String, ParamPtr>,
+    ids_by_param: HashMap<ParamPtr, String>,
+    /// The parameter that's waiting for a CC.
+    learning: Option<ParamPtr>,
+    /// The mapped parameters the widgets were last told about.
+    mapped: Vec<ParamPtr>,
+}
+
+pub enum MidiLearnEvent {
+    /// Bind the next CC that comes in to this parameter.
+    Learn(ParamPtr),
+    CancelLearn,
+    /// Remove the parameter's mapping.
+    Forget(ParamPtr),
+}
+
+/// Emitted every [`POLL_INTERVAL`] to bind the CCs in the queue.
+struct PollEvent;
+
+/// Asks the [`MidiLearner`] to send a [`MidiLearnStatus`]. Param widgets emit this when they're
+/// built.
+pub(crate) struct MidiLearnStatusRequest;
+
+/// Sent to every view when the parameter that's learning or the mapped parameters change.
+pub(crate) struct MidiLearnStatus {
+    pub learning: Option<ParamPtr>,
+    pub mapped: Vec<ParamPtr>,
+}
+
+impl MidiLearner {
+    /// Build the learner at the root of the editor, and start checking for CCs to bind.
+    pub fn new(cx: &mut Context, params: &dyn Params, midi_learn: Arc<MidiLearn>) {
+        let param_map = params.param_map();
+        // A previous editor may have been closed while a parameter was learning
+        midi_learn.stop_learning();
+
+        Self {
+            midi_learn,
+            params_by_id: param_map
+                .iter()
+                .map(|(id, param_ptr, _)| (id.clone(), *param_ptr))
+                .collect(),
+            ids_by_param: param_map
+                .into_iter()
+                .map(|(id, param_ptr, _)| (param_ptr, id))
+                .collect(),
+            learning: None,
+            mapped: Vec::new(),
+        }
+        .build(cx);
+
+        cx.spawn(|cx| loop {
+            thread::sleep(POLL_INTERVAL);
+
+            // This fails once the editor has been closed
+            if cx.emit(PollEvent).is_err() {
+                break;
+            }
+        });
+    }
+
+    /// "MIDI LEARN" and "FORGET MIDI" entries for the
+    /// [`ParamContextMenu`][crate::param_menu::ParamContextMenu].
+    pub fn menu_entries() -> Vec<ParamMenuEntry> {
+        vec![
+            ParamMenuEntry::new("MIDI LEARN", |cx, param| {
+                cx.emit(MidiLearnEvent::Learn(param))
+            }),
+            ParamMenuEntry::new("FORGET MIDI", |cx, param| {
+                cx.emit(MidiLearnEvent::Forget(param))
+            }),
+        ]
+    }
+
+    fn set_learning(&mut self, learning: Option<ParamPtr>) {
+        self.learning = learning;
+        self.midi_learn
+            .learning
+            .store(learning.is_some(), Ordering::Relaxed);
+    }
+
+    fn mapped_params(&self) -> Vec<ParamPtr> {
+        let mappings = self.midi_learn.mappings.read().unwrap();
+        mappings
+            .iter()
+            .filter_map(|mapping| self.params_by_id.get(&mapping.param_id).copied())
+            .collect()
+    }
+
+    fn send_status(&mut self, cx: &mut EventContext) {
+        self.mapped = self.mapped_params();
+        cx.emit_custom(
+            Event::new(MidiLearnStatus {
+                learning: self.learning,
+                mapped: self.mapped.clone(),
+            })
+            .target(Entity::root())
+            .propagate(Propagation::Subtree),
+        );
+    }
+
+    /// Bind the first CC in the queue to the parameter that's learning.
+    fn poll(&mut self, cx: &mut EventContext) {
+        let Some(param) = self.learning else {
+            // Anything left over from before learning was cancelled is stale
+            while self.midi_learn.incoming.pop().is_some() {}
+            return;
+        };
+        let Some(MidiCc { channel, cc, .. }) = self.midi_learn.incoming.pop() else {
+            return;
+        };
+
+        if let Some(param_id) = self.ids_by_param.get(&param) {
+            self.midi_learn.bind(channel, cc, param_id);
+        }
+        self.set_learning(None);
+        self.send_status(cx);
+    }
+}
+
+impl Model for MidiLearner {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|learn_event, meta| {
+            match learn_event {
+                MidiLearnEvent::Learn(param) => self.set_learning(Some(*param)),
+                MidiLearnEvent::CancelLearn => self.set_learning(None),
+                MidiLearnEvent::Forget(param) => {
+                    if let Some(param_id) = self.ids_by_param.get(param) {
+                        self.midi_learn.unbind(param_id);
+                    }
+                }
+            }
+
+            self.send_status(cx);
+            meta.consume();
+        });
+
+        event.map(|_: &PollEvent, meta| {
+            self.poll(cx);
+            meta.consume();
+        });
+
+        event.map(|_: &MidiLearnStatusRequest, meta| {
+            self.send_status(cx);
+            meta.consume();
+        });
+
+        // Loading the plugin's state can replace the mappings
+        event.map(|param_event: &RawParamEvent, _| {
+            if matches!(param_event, RawParamEvent::ParametersChanged)
+                && self.mapped_params() != self.mapped
+            {
+                self.send_status(cx);
+            }
+        });
+    }
+}
+
+impl Drop for MidiLearner {
+    fn drop(&mut self) {
+        // Otherwise the plugin keeps sending every CC to an editor that's gone
+        self.midi_learn.stop_learning();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nih_plug::prelude::*;
+
+    use super::*;
+
+    #[derive(Params)]
+    struct TestParams {
+        #[id = "gain"]
+        gain: FloatParam,
+    }
+
+    fn test_params() -> TestParams {
+        TestParams {
+            gain: FloatParam::new(
+                "Gain",
+                0.0,
+                FloatRange::Linear {
+                    min: -30.0,
+                    max: 0.0,
+                },
+            ),
+        }
+    }
+
+    fn cc(cc: u8, value: f32) -> NoteEvent<()> {
+        NoteEvent::MidiCC {
+            timing: 0,
+            channel: 0,
+            cc,
+            value,
+        }
+    }
+
+    #[test]
+    fn mapped_cc_sets_param_after_abandoned_learn() {
+        let params = test_params();
+        let midi_learn = MidiLearn::new();
+        midi_learn.register_params(&params);
+        midi_learn.bind(0, 7, "gain");
+
+        midi_learn.learning.store(true, Ordering::Relaxed);
+        assert_eq!(
+            midi_learn.process_event(&cc(7, 0.25)),
+            MidiCcAction::Learned
+        );
+
+        // What tearing down the editor's learner does
+        midi_learn.stop_learning();
+        assert!(midi_learn.incoming.is_empty());
+        assert_eq!(
+            midi_learn.process_event(&cc(7, 0.5)),
+            MidiCcAction::Set(params.gain.as_ptr(), 0.5)
+        );
+        assert_eq!(midi_learn.process_event(&cc(8, 0.5)), MidiCcAction::None);
+    }
+
+    #[test]
+    fn rebinding_keeps_other_targets() {
+        let params = test_params();
+        let midi_learn = MidiLearn::new();
+        midi_learn.register_params(&params);
+        midi_learn.bind(0, 7, "gain");
+        midi_learn.bind(0, 9, "gain");
+
+        assert_eq!(midi_learn.process_event(&cc(7, 0.5)), MidiCcAction::None);
+        assert_eq!(
+            midi_learn.process_event(&cc(9, 0.5)),
+            MidiCcAction::Set(params.gain.as_ptr(), 0.5)
+        );
+    }
+
+    #[test]
+    fn cc_override_only_takes_its_own_param() {
+        let params = test_params();
+        let mut cc_override = CcOverride::new();
+        assert_eq!(cc_override.value(&params.gain), None);
+
+        assert!(!cc_override.apply(&params.gain, MidiCcAction::Learned));
+        assert!(cc_override.apply(&params.gain, MidiCcAction::Set(params.gain.as_ptr(), 0.5)));
+        assert_eq!(cc_override.value(&params.gain), Some(-15.0));
+        assert_eq!(cc_override.value(&params.gain), Some(-15.0));
+    }
+}
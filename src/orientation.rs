@@ -0,0 +1,12 @@
+use nih_plug_vizia::vizia::prelude::*;
+
+/// Whether a widget is laid out horizontally, or vertically like a channel fader. This is shared by
+/// [`ParamSlider`][crate::param_slider::ParamSlider]s, selectors, and
+/// [`LevelMeter`][crate::level_meter::LevelMeter]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
+pub enum Orientation {
+    #[default]
+    Horizontal,
+    /// Sliders fill from the bottom up, with the name above and the value below the track.
+    Vertical,
+}
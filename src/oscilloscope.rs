@@ -0,0 +1,319 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+use crate::bridge::{redraw_continuously, RedrawEvent, RingBufferOutput};
+
+/// Draws the most recent samples of one or more channels as a waveform. The plugin pushes one
+/// `[f32; C]` frame per sample into a [`ring_buffer()`][crate::bridge::ring_buffer], where `C` is
+/// the number of channels. Every channel is drawn in its own color, which can be styled through
+/// the `channel-0`, `channel-1`, ... classes.
+///
+/// Long time windows are decimated into blocks of samples as they come in, and every block is
+/// drawn as the range between its lowest and its highest sample, so drawing stays cheap no matter
+/// how many samples are in the window.
+#[derive(Lens)]
+pub struct Oscilloscope {
+    time_window_ms: f32,
+    trigger: TriggerMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
+pub enum TriggerMode {
+    /// Always show the most recent samples.
+    #[default]
+    FreeRun,
+    /// Start the window at the most recent point where the first channel crosses zero going
+    /// upwards, so periodic signals stand still.
+    RisingEdge,
+}
+
+const DEFAULT_TIME_WINDOW: Duration = Duration::from_millis(20);
+
+/// The window gets decimated into blocks of samples once it holds more than this many samples.
+const RESOLUTION: usize = 1024;
+
+impl Oscilloscope {
+    /// Create an oscilloscope for the frames in `samples`, which were recorded at `sample_rate`.
+    pub fn new<S, R, const C: usize>(cx: &mut Context, samples: S, sample_rate: R) -> Handle<Self>
+    where
+        S: Lens<Target = RingBufferOutput<[f32; C]>>,
+        R: Lens<Target = f32>,
+    {
+        let state = Rc::new(RefCell::new(ScopeState::new(C)));
+
+        Self {
+            time_window_ms: DEFAULT_TIME_WINDOW.as_secs_f32() * 1000.0,
+            trigger: TriggerMode::default(),
+        }
+        .build(cx, |cx| {
+            // The ring buffer is only drained while the waveform is drawn
+            redraw_continuously(cx);
+
+            ZStack::new(cx, |cx| {
+                ScopeInput {
+                    samples,
+                    sample_rate,
+                    state: state.clone(),
+                }
+                .build(cx, |_| {});
+
+                ScopeLayer {
+                    channel: None,
+                    state: state.clone(),
+                }
+                .build(cx, |_| {})
+                .class("center-line");
+
+                for channel in 0..C {
+                    ScopeLayer {
+                        channel: Some(channel),
+                        state: state.clone(),
+                    }
+                    .build(cx, |_| {})
+                    .class("channel")
+                    .class(&format!("channel-{channel}"));
+                }
+            })
+            .class("display")
+            .hoverable(false);
+        })
+    }
+}
+
+pub trait OscilloscopeModifiers {
+    /// The amount of time the oscilloscope shows at once. Defaults to 20 ms.
+    fn time_window(self, time_window: Duration) -> Self;
+
+    /// When the displayed window starts. Defaults to [`TriggerMode::FreeRun`].
+    fn trigger(self, trigger: TriggerMode) -> Self;
+}
+
+impl OscilloscopeModifiers for Handle<'_, Oscilloscope> {
+    fn time_window(self, time_window: Duration) -> Self {
+        self.modify(|scope| scope.time_window_ms = time_window.as_secs_f32() * 1000.0)
+    }
+
+    fn trigger(self, trigger: TriggerMode) -> Self {
+        self.modify(|scope| scope.trigger = trigger)
+    }
+}
+
+impl View for Oscilloscope {
+    fn element(&self) -> Option<&'static str> {
+        Some("oscilloscope")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|RedrawEvent, meta| {
+            cx.needs_redraw();
+            meta.consume();
+        });
+    }
+}
+
+/// The lowest and highest sample within a block. Without decimation both are the same sample.
+#[derive(Debug, Clone, Copy, Default)]
+struct Block {
+    min: f32,
+    max: f32,
+}
+
+impl Block {
+    fn mid(&self) -> f32 {
+        (self.min + self.max) / 2.0
+    }
+}
+
+/// The decimated history shared between the oscilloscope's layers. This is updated by the
+/// [`ScopeInput`], which is drawn first, once per frame.
+struct ScopeState {
+    /// Two windows' worth of blocks for every channel, so the trigger has room to look back. The
+    /// oldest block is at `history_pos`.
+    history: Vec<Vec<Block>>,
+    history_pos: usize,
+    /// The block that's currently being filled for every channel.
+    pending: Vec<Block>,
+    pending_len: usize,
+
+    /// The number of samples in a block.
+    decimation: usize,
+    /// The number of blocks in the displayed window.
+    window_blocks: usize,
+    /// The first block of the displayed window, counting from the oldest block in the history.
+    window_start: usize,
+}
+
+impl ScopeState {
+    fn new(channels: usize) -> Self {
+        Self {
+            history: vec![Vec::new(); channels],
+            history_pos: 0,
+            pending: vec![Block::default(); channels],
+            pending_len: 0,
+            decimation: 1,
+            window_blocks: 0,
+            window_start: 0,
+        }
+    }
+
+    /// Clear the history if the time window or the sample rate changed.
+    fn resize(&mut self, window_samples: usize) {
+        let window_samples = window_samples.max(2);
+        let decimation = window_samples.div_ceil(RESOLUTION);
+        let window_blocks = window_samples.div_ceil(decimation);
+        if decimation == self.decimation && window_blocks == self.window_blocks {
+            return;
+        }
+
+        self.decimation = decimation;
+        self.window_blocks = window_blocks;
+        self.window_start = window_blocks;
+        self.history_pos = 0;
+        self.pending_len = 0;
+        for history in &mut self.history {
+            *history = vec![Block::default(); window_blocks * 2];
+        }
+    }
+
+    fn push(&mut self, frame: &[f32]) {
+        for (pending, &sample) in self.pending.iter_mut().zip(frame) {
+            *pending = if self.pending_len == 0 {
+                Block {
+                    min: sample,
+                    max: sample,
+                }
+            } else {
+                Block {
+                    min: pending.min.min(sample),
+                    max: pending.max.max(sample),
+                }
+            };
+        }
+
+        self.pending_len += 1;
+        if self.pending_len == self.decimation {
+            for (history, pending) in self.history.iter_mut().zip(&self.pending) {
+                history[self.history_pos] = *pending;
+            }
+
+            self.history_pos = (self.history_pos + 1) % (self.window_blocks * 2);
+            self.pending_len = 0;
+        }
+    }
+
+    /// The `i`th block of the history, counting from the oldest one.
+    fn block(&self, channel: usize, i: usize) -> Block {
+        let history = &self.history[channel];
+        history[(self.history_pos + i) % history.len()]
+    }
+
+    /// Pick the window to display based on the trigger mode.
+    fn trigger(&mut self, trigger: TriggerMode) {
+        let latest_start = self.window_blocks;
+        self.window_start = match trigger {
+            TriggerMode::FreeRun => latest_start,
+            TriggerMode::RisingEdge if !self.history.is_empty() => (1..=latest_start)
+                .rev()
+                .find(|&i| self.block(0, i - 1).mid() < 0.0 && self.block(0, i).mid() >= 0.0)
+                .unwrap_or(latest_start),
+            TriggerMode::RisingEdge => latest_start,
+        };
+    }
+}
+
+/// Drains the ring buffer into the [`ScopeState`]. This doesn't draw anything itself.
+struct ScopeInput<S, R> {
+    samples: S,
+    sample_rate: R,
+    state: Rc<RefCell<ScopeState>>,
+}
+
+impl<S, R, const C: usize> View for ScopeInput<S, R>
+where
+    S: Lens<Target = RingBufferOutput<[f32; C]>>,
+    R: Lens<Target = f32>,
+{
+    fn draw(&self, cx: &mut DrawContext, _canvas: &mut Canvas) {
+        let time_window_ms = Oscilloscope::time_window_ms.get(cx);
+        let trigger = Oscilloscope::trigger.get(cx);
+        let sample_rate = self.sample_rate.get(cx);
+
+        let mut state = self.state.borrow_mut();
+        state.resize((time_window_ms / 1000.0 * sample_rate).round() as usize);
+
+        let mut received = false;
+        for frame in self.samples.get(cx).drain() {
+            state.push(&frame);
+            received = true;
+        }
+
+        if received {
+            state.trigger(trigger);
+        }
+    }
+}
+
+struct ScopeLayer {
+    /// The channel to draw with the `color` and `border-width`, or the center line if this is
+    /// `None`.
+    channel: Option<usize>,
+    state: Rc<RefCell<ScopeState>>,
+}
+
+impl View for ScopeLayer {
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let mut paint = vg::Paint::color(cx.font_color().into());
+        paint.set_line_width(cx.border_width().max(cx.scale_factor()));
+
+        let center = bounds.y + bounds.h / 2.0;
+        let mut path = vg::Path::new();
+        let Some(channel) = self.channel else {
+            let y = center.round() + 0.5;
+            path.move_to(bounds.x, y);
+            path.line_to(bounds.x + bounds.w, y);
+            canvas.stroke_path(&path, &paint);
+
+            return;
+        };
+
+        let state = self.state.borrow();
+        if state.window_blocks < 2 || channel >= state.history.len() {
+            return;
+        }
+
+        let sample_to_y = |sample: f32| center - sample.clamp(-1.0, 1.0) * bounds.h / 2.0;
+        let block_to_x =
+            |i: usize| bounds.x + bounds.w * i as f32 / (state.window_blocks - 1) as f32;
+        let blocks = (0..state.window_blocks).map(|i| state.block(channel, state.window_start + i));
+
+        if state.decimation == 1 {
+            for (i, block) in blocks.enumerate() {
+                if i == 0 {
+                    path.move_to(block_to_x(i), sample_to_y(block.max));
+                } else {
+                    path.line_to(block_to_x(i), sample_to_y(block.max));
+                }
+            }
+        } else {
+            // Every block becomes a vertical line from its lowest to its highest sample, joined to
+            // the next block
+            for (i, block) in blocks.enumerate() {
+                let x = block_to_x(i);
+                if i == 0 {
+                    path.move_to(x, sample_to_y(block.min));
+                } else {
+                    path.line_to(x, sample_to_y(block.min));
+                }
+                path.line_to(x, sample_to_y(block.max));
+            }
+        }
+
+        canvas.stroke_path(&path, &paint);
+    }
+}
@@ -1,16 +1,59 @@
 use nih_plug::prelude::*;
 use nih_plug_vizia::{
     vizia::{icons::ICON_CHEVRON_DOWN, prelude::*},
-    widgets::param_base::ParamWidgetBase,
+    widgets::param_base::{ParamWidgetBase, ParamWidgetData},
 };
 
-enum SelectorEvent {
+use crate::{param_menu::ParamMenuTarget, param_value};
+
+/// How many rows of the list are built at a time. Scrolling moves these rows through the list
+/// instead of building a label for every step.
+const VISIBLE_ROWS: usize = 8;
+
+enum DropdownEvent {
     SetTo(f32),
+    /// Sent from inside the popup when it opens or closes.
+    SetOpen(bool),
+    DropdownBuilt(Entity),
+}
+
+/// A row in the popup's list.
+#[derive(Debug, Clone, PartialEq)]
+enum DropdownRow {
+    Header(String),
+    Option { value: f32, label: String },
 }
 
+impl Data for DropdownRow {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// A dropdown with every step of a discrete parameter. While it's open, typing filters the steps,
+/// the up and down keys move through them and Enter selects one. Only the rows that are visible are
+/// built, so parameters with hundreds of steps are fine.
 #[derive(Lens)]
 pub struct ParamDropdown {
     param_base: ParamWidgetBase,
+    context_menu: ParamMenuTarget,
+
+    /// What's been typed to filter the list since it was opened.
+    query: String,
+    /// The steps that match the query, under the headers of their groups.
+    rows: Vec<DropdownRow>,
+    /// The row that Enter selects.
+    highlighted: Option<usize>,
+    /// The first visible row.
+    offset: usize,
+    open: bool,
+
+    /// Every step's normalized value and label.
+    options: Vec<(f32, String)>,
+    /// The headers set with [`ParamDropdownModifiers::groups()`], with the index of the step each
+    /// group starts at.
+    groups: Vec<(usize, String)>,
+    dropdown: Option<Entity>,
 }
 
 impl ParamDropdown {
@@ -25,8 +68,30 @@ impl ParamDropdown {
         P: Param + 'static,
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
+        let options = params
+            .clone()
+            .map(move |params| {
+                let param = params_to_param(params);
+                let step_count = param.step_count().unwrap_or_default();
+
+                (0..=step_count)
+                    .map(|v| v as f32 / step_count as f32)
+                    .map(|value| (value, param.normalized_value_to_string(value, false)))
+                    .collect::<Vec<_>>()
+            })
+            .get(cx);
+
         Self {
-            param_base: ParamWidgetBase::new(cx, params, params_to_param),
+            context_menu: ParamMenuTarget::new(cx, params.clone(), params_to_param),
+            param_base: ParamWidgetBase::new(cx, params.clone(), params_to_param),
+            query: String::new(),
+            rows: Vec::new(),
+            highlighted: None,
+            offset: 0,
+            open: false,
+            options,
+            groups: Vec::new(),
+            dropdown: None,
         }
         .build(
             cx,
@@ -39,7 +104,7 @@ impl ParamDropdown {
                                 cx,
                                 param_data.make_lens(|p| {
                                     p.normalized_value_to_string(
-                                        p.modulated_normalized_value(),
+                                        p.unmodulated_normalized_value(),
                                         true,
                                     )
                                 }),
@@ -49,6 +114,18 @@ impl ParamDropdown {
                             .checked(PopupData::is_open)
                             .on_press(|cx| cx.emit(PopupEvent::Switch))
                             .class("value");
+                            Label::new(
+                                cx,
+                                param_data.make_lens(|p| {
+                                    p.normalized_value_to_string(
+                                        p.modulated_normalized_value(),
+                                        true,
+                                    )
+                                }),
+                            )
+                            .display(param_data.make_lens(param_value::is_modulated))
+                            .pointer_events(false)
+                            .class("modulation");
                             Label::new(cx, ICON_CHEVRON_DOWN)
                                 .role(Role::PopupButton)
                                 .cursor(CursorIcon::Hand)
@@ -57,29 +134,258 @@ impl ParamDropdown {
                         })
                     },
                     move |cx| {
-                        let step_count = param_data.param().step_count().unwrap_or_default();
-
-                        for value in (0..=step_count).map(|v| v as f32 / step_count as f32) {
-                            let formatted =
-                                param_data.param().normalized_value_to_string(value, false);
-
-                            Label::new(cx, formatted.as_str())
-                                .on_press(move |cx| {
-                                    cx.emit(SelectorEvent::SetTo(value));
-                                    cx.emit(PopupEvent::Close)
-                                })
-                                .toggle_class(
-                                    "selected",
-                                    param_data.make_lens(move |p| {
-                                        p.modulated_normalized_value() == value
-                                    }),
-                                );
-                        }
+                        Binding::new(cx, PopupData::is_open, |cx, is_open| {
+                            let is_open = is_open.get(cx);
+                            cx.emit(DropdownEvent::SetOpen(is_open));
+                        });
+
+                        Label::new(
+                            cx,
+                            ParamDropdown::query.map(|query| {
+                                if query.is_empty() {
+                                    String::from("TYPE TO SEARCH")
+                                } else {
+                                    query.to_uppercase()
+                                }
+                            }),
+                        )
+                        .class("search")
+                        .toggle_class("empty", ParamDropdown::query.map(String::is_empty));
+
+                        HStack::new(cx, move |cx| {
+                            Binding::new(cx, ParamDropdown::rows, move |cx, rows| {
+                                Binding::new(cx, ParamDropdown::offset, move |cx, offset| {
+                                    let rows = rows.get(cx);
+                                    list(cx, param_data, &rows, offset.get(cx));
+                                });
+                            });
+                        })
+                        .class("list");
                     },
-                );
+                )
+                .on_build(|cx| cx.emit(DropdownEvent::DropdownBuilt(cx.current())));
             }),
         )
     }
+
+    /// Rebuild the rows from the steps that match the query, and highlight the first one.
+    fn filter(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut rows = Vec::new();
+        let mut header = None;
+        for (i, (value, label)) in self.options.iter().enumerate() {
+            if let Some((_, name)) = self.groups.iter().find(|(start, _)| *start == i) {
+                header = Some(name.clone());
+            }
+            if !label.to_lowercase().contains(&query) {
+                continue;
+            }
+
+            // Groups without any matches are left out entirely
+            if let Some(name) = header.take() {
+                rows.push(DropdownRow::Header(name));
+            }
+            rows.push(DropdownRow::Option {
+                value: *value,
+                label: label.clone(),
+            });
+        }
+
+        self.rows = rows;
+        self.offset = 0;
+        self.highlighted = self.next_option(0, true);
+    }
+
+    /// The first row at or after (or before) `start` that's a step and not a header.
+    fn next_option(&self, start: usize, forward: bool) -> Option<usize> {
+        let is_option = |i: &usize| matches!(self.rows[*i], DropdownRow::Option { .. });
+        if self.rows.is_empty() {
+            None
+        } else if forward {
+            (start..self.rows.len()).find(is_option)
+        } else {
+            (0..=start.min(self.rows.len().saturating_sub(1)))
+                .rev()
+                .find(is_option)
+        }
+    }
+
+    /// Highlight the step closest to the parameter's value, and scroll it into the middle of the
+    /// list.
+    fn highlight_current(&mut self) {
+        let current = self.param_base.unmodulated_normalized_value();
+        self.highlighted = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, row)| match row {
+                DropdownRow::Option { value, .. } => Some((i, (value - current).abs())),
+                DropdownRow::Header(_) => None,
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+
+        if let Some(highlighted) = self.highlighted {
+            self.scroll_to(highlighted.saturating_sub(VISIBLE_ROWS / 2));
+        }
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.offset = offset.min(self.rows.len().saturating_sub(VISIBLE_ROWS));
+    }
+
+    /// Scroll just far enough for the highlighted row to be visible.
+    fn scroll_to_highlighted(&mut self) {
+        let Some(highlighted) = self.highlighted else {
+            return;
+        };
+
+        if highlighted < self.offset {
+            // Keep a header that's right above the row in view
+            let header =
+                highlighted > 0 && matches!(self.rows[highlighted - 1], DropdownRow::Header(_));
+            self.scroll_to(highlighted - usize::from(header));
+        } else if highlighted >= self.offset + VISIBLE_ROWS {
+            self.scroll_to(highlighted + 1 - VISIBLE_ROWS);
+        }
+    }
+
+    fn set(&self, cx: &mut EventContext, normalized_value: f32) {
+        self.param_base.begin_set_parameter(cx);
+        self.param_base.set_normalized_value(cx, normalized_value);
+        self.param_base.end_set_parameter(cx);
+    }
+
+    fn close(&self, cx: &mut EventContext) {
+        if let Some(dropdown) = self.dropdown {
+            cx.emit_to(dropdown, PopupEvent::Close);
+        }
+    }
+
+    fn handle_key(&mut self, cx: &mut EventContext, code: Code) -> bool {
+        match code {
+            Code::ArrowDown => {
+                let start = self.highlighted.map_or(0, |highlighted| highlighted + 1);
+                if let Some(next) = self.next_option(start, true) {
+                    self.highlighted = Some(next);
+                }
+            }
+            Code::ArrowUp => {
+                let previous = self
+                    .highlighted
+                    .and_then(|highlighted| highlighted.checked_sub(1))
+                    .and_then(|start| self.next_option(start, false));
+                if let Some(previous) = previous {
+                    self.highlighted = Some(previous);
+                }
+            }
+            Code::Enter | Code::NumpadEnter => {
+                if let Some(&DropdownRow::Option { value, .. }) = self
+                    .highlighted
+                    .and_then(|highlighted| self.rows.get(highlighted))
+                {
+                    self.set(cx, value);
+                }
+                self.close(cx);
+            }
+            Code::Escape => self.close(cx),
+            Code::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            _ => return false,
+        }
+
+        self.scroll_to_highlighted();
+        true
+    }
+}
+
+/// The visible rows of the list, and a scrollbar if there are more.
+fn list<L, Params, P, FMap>(
+    cx: &mut Context,
+    param_data: ParamWidgetData<L, Params, P, FMap>,
+    rows: &[DropdownRow],
+    offset: usize,
+) where
+    L: Lens<Target = Params> + Clone,
+    Params: 'static,
+    P: Param + 'static,
+    FMap: Fn(&Params) -> &P + Copy + 'static,
+{
+    if rows.is_empty() {
+        Label::new(cx, "NO MATCHES").class("no-matches");
+        return;
+    }
+
+    VStack::new(cx, |cx| {
+        for (i, row) in rows.iter().enumerate().skip(offset).take(VISIBLE_ROWS) {
+            match row {
+                DropdownRow::Header(name) => {
+                    Label::new(cx, name.as_str()).class("header");
+                }
+                DropdownRow::Option { value, label } => {
+                    let value = *value;
+                    Label::new(cx, label.as_str())
+                        .on_press(move |cx| {
+                            cx.emit(DropdownEvent::SetTo(value));
+                            cx.emit(PopupEvent::Close)
+                        })
+                        .class("value")
+                        .toggle_class(
+                            "selected",
+                            param_data.make_lens(move |p| param_value::is_selected(p, value)),
+                        )
+                        .toggle_class(
+                            "modulated",
+                            param_data.make_lens(move |p| param_value::is_modulated_to(p, value)),
+                        )
+                        .toggle_class(
+                            "highlighted",
+                            ParamDropdown::highlighted
+                                .map(move |highlighted| *highlighted == Some(i)),
+                        );
+                }
+            }
+        }
+    })
+    .class("rows");
+
+    if rows.len() > VISIBLE_ROWS {
+        let len = rows.len() as f32;
+        VStack::new(cx, |cx| {
+            Element::new(cx)
+                .class("thumb")
+                .top(Percentage(offset as f32 / len * 100.0))
+                .height(Percentage(VISIBLE_ROWS as f32 / len * 100.0));
+        })
+        .class("scrollbar");
+    }
+}
+
+pub trait ParamDropdownModifiers {
+    /// Show headers in the list. Every group is an index of the step it starts at, and its header.
+    /// Groups are left out while none of their steps match the search.
+    ///
+    /// ```ignore
+    /// ParamDropdown::new(cx, Data::params, |p| &p.waveform)
+    ///     .groups([(0, "BASIC"), (4, "WAVETABLE"), (36, "NOISE")]);
+    /// ```
+    fn groups<S: Into<String>>(self, groups: impl IntoIterator<Item = (usize, S)>) -> Self;
+}
+
+impl ParamDropdownModifiers for Handle<'_, ParamDropdown> {
+    fn groups<S: Into<String>>(self, groups: impl IntoIterator<Item = (usize, S)>) -> Self {
+        let groups = groups
+            .into_iter()
+            .map(|(start, name)| (start, name.into()))
+            .collect();
+
+        self.modify(|dropdown| {
+            dropdown.groups = groups;
+            dropdown.filter();
+        })
+    }
 }
 
 impl View for ParamDropdown {
@@ -87,13 +393,54 @@ impl View for ParamDropdown {
         Some("paramdropdown")
     }
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        event.map(|param_slider_event, meta| match param_slider_event {
-            SelectorEvent::SetTo(x) => {
-                self.param_base.begin_set_parameter(cx);
-                self.param_base.set_normalized_value(cx, *x);
-                self.param_base.end_set_parameter(cx);
+        if self.context_menu.handle_event(cx, event) {
+            return;
+        }
+
+        event.map(|dropdown_event, meta| {
+            match dropdown_event {
+                DropdownEvent::SetTo(x) => self.set(cx, *x),
+                DropdownEvent::SetOpen(open) => {
+                    self.open = *open;
+                    if *open {
+                        self.query.clear();
+                        self.filter();
+                        self.highlight_current();
+
+                        // The typed text and the keys go to the focused view
+                        cx.focus();
+                    }
+                }
+                DropdownEvent::DropdownBuilt(dropdown) => self.dropdown = Some(*dropdown),
+            }
+
+            meta.consume();
+        });
+
+        if !self.open {
+            return;
+        }
+
+        event.map(|window_event: &WindowEvent, meta| match window_event {
+            WindowEvent::KeyDown(code, _) => {
+                if self.handle_key(cx, *code) {
+                    meta.consume();
+                }
+            }
+            WindowEvent::CharInput(c) if !c.is_control() => {
+                self.query.push(*c);
+                self.filter();
+                meta.consume();
+            }
+            WindowEvent::MouseScroll(_, y) if *y != 0.0 => {
+                if *y > 0.0 {
+                    self.scroll_to(self.offset.saturating_sub(1));
+                } else {
+                    self.scroll_to(self.offset + 1);
+                }
                 meta.consume();
             }
+            _ => {}
         });
     }
 }
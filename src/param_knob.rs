@@ -0,0 +1,486 @@
+use std::f32::consts::PI;
+
+use nih_plug::prelude::*;
+use nih_plug_vizia::{
+    vizia::{prelude::*, vg},
+    widgets::param_base::ParamWidgetBase,
+};
+
+use crate::{param_menu::ParamMenuTarget, param_slider::SliderTick};
+
+/// A rotary knob for a parameter. Dragging up and down changes the value, holding shift while
+/// dragging makes it more granular.
+#[derive(Lens)]
+pub struct ParamKnob {
+    param_base: ParamWidgetBase,
+    context_menu: ParamMenuTarget,
+    drag_status: Option<KnobDragStatus>,
+    pub text_input_active: bool,
+    scrolled_lines: f32,
+    sweep: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KnobDragStatus {
+    pub starting_y_coordinate: f32,
+    pub starting_value: f32,
+    pub granular: bool,
+}
+
+/// The number of logical pixels the mouse needs to travel to go through the entire range.
+const DRAG_DISTANCE: f32 = 200.0;
+const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
+const DEFAULT_SWEEP: f32 = 270.0;
+
+impl ParamKnob {
+    pub fn new<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        ticks: impl IntoIterator<Item = SliderTick>,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        Self {
+            context_menu: ParamMenuTarget::new(cx, params.clone(), params_to_param),
+            param_base: ParamWidgetBase::new(cx, params, params_to_param),
+            drag_status: None,
+            text_input_active: false,
+            scrolled_lines: 0.0,
+            sweep: DEFAULT_SWEEP,
+        }
+        .build(
+            cx,
+            ParamWidgetBase::build_view(params, params_to_param, move |cx, param_data| {
+                let unmodulated_normalized_value_lens =
+                    param_data.make_lens(|param| param.unmodulated_normalized_value());
+                let modulated_normalized_value_lens =
+                    param_data.make_lens(|param| param.modulated_normalized_value());
+                let display_value_lens = param_data.make_lens(|param| {
+                    param.normalized_value_to_string(param.unmodulated_normalized_value(), true)
+                });
+                let default_value = param_data.param().default_normalized_value();
+                let ticks: Vec<SliderTick> = ticks.into_iter().collect();
+
+                HStack::new(cx, |cx| {
+                    Label::new(cx, param_data.param().name().to_uppercase().as_str()).class("name");
+                    Binding::new(
+                        cx,
+                        ParamKnob::text_input_active,
+                        move |cx, text_input_active| {
+                            if text_input_active.get(cx) {
+                                Textbox::new(cx, display_value_lens)
+                                    .on_submit(|cx, string, success| {
+                                        if success {
+                                            cx.emit(KnobEvent::TextInput(string))
+                                        } else {
+                                            cx.emit(KnobEvent::CancelTextInput);
+                                        }
+                                        cx.focus();
+                                    })
+                                    .on_focus_out(|cx| {
+                                        cx.emit(KnobEvent::CancelTextInput);
+                                    })
+                                    .on_cancel(|cx| {
+                                        cx.emit(KnobEvent::CancelTextInput);
+                                        cx.focus();
+                                    })
+                                    .on_build(|cx| {
+                                        cx.emit(TextEvent::StartEdit);
+                                        cx.emit(TextEvent::SelectAll);
+                                    });
+                            } else {
+                                Label::new(cx, display_value_lens).class("value");
+                            }
+                        },
+                    );
+                })
+                .class("title");
+
+                ZStack::new(cx, move |cx| {
+                    let arc = |kind| KnobArc {
+                        kind,
+                        value: unmodulated_normalized_value_lens,
+                        modulated_value: modulated_normalized_value_lens,
+                        default_value,
+                        sweep: ParamKnob::sweep,
+                    };
+
+                    arc(KnobArcKind::Track)
+                        .build(cx, |_| {})
+                        .class("track")
+                        .hoverable(false);
+                    arc(KnobArcKind::Fill)
+                        .build(cx, |_| {})
+                        .class("fill")
+                        .hoverable(false);
+                    arc(KnobArcKind::Modulation)
+                        .build(cx, |_| {})
+                        .class("modulation")
+                        .hoverable(false);
+                    arc(KnobArcKind::Head)
+                        .build(cx, |_| {})
+                        .class("head")
+                        .hoverable(false);
+
+                    if !ticks.is_empty() {
+                        arc(KnobArcKind::Ticks(
+                            ticks.iter().map(|tick| (tick.pos, tick.short)).collect(),
+                        ))
+                        .build(cx, |_| {})
+                        .class("ticks")
+                        .hoverable(false);
+
+                        for tick in ticks.iter() {
+                            let Some(label) = tick.label.as_ref() else {
+                                continue;
+                            };
+                            let pos = tick.pos;
+
+                            // Labels sit just outside of the ring, centered on the tick's angle
+                            Label::new(cx, label)
+                                .class("tick-label")
+                                .width(Pixels(1.0))
+                                .height(Pixels(1.0))
+                                .text_align(TextAlign::Center)
+                                .left(ParamKnob::sweep.map(move |sweep| {
+                                    Percentage(50.0 + 62.0 * knob_angle(*sweep, pos).cos())
+                                }))
+                                .top(ParamKnob::sweep.map(move |sweep| {
+                                    Percentage(50.0 + 62.0 * knob_angle(*sweep, pos).sin())
+                                }))
+                                .hoverable(false);
+                        }
+                    }
+                })
+                .class("knob");
+            }),
+        )
+        .navigable(true)
+    }
+
+    /// Rebase the drag so that moving the mouse from `y_coordinate` changes the value relative to
+    /// the current value. This happens when starting a drag and whenever shift is pressed or
+    /// released during one.
+    fn start_drag(&mut self, y_coordinate: f32, granular: bool) {
+        self.drag_status = Some(KnobDragStatus {
+            starting_y_coordinate: y_coordinate,
+            starting_value: self.param_base.unmodulated_normalized_value(),
+            granular,
+        });
+    }
+}
+
+pub trait ParamKnobModifiers {
+    /// The angle in degrees the knob's arc covers. Defaults to 270 degrees, with the gap at the
+    /// bottom.
+    fn sweep(self, degrees: f32) -> Self;
+}
+
+impl ParamKnobModifiers for Handle<'_, ParamKnob> {
+    fn sweep(self, degrees: f32) -> Self {
+        self.modify(|knob| knob.sweep = degrees.clamp(0.0, 360.0))
+    }
+}
+
+/// The angle in radians for a normalized value on a knob with the given sweep in degrees. The arc
+/// is centered at the top of the knob.
+fn knob_angle(sweep: f32, t: f32) -> f32 {
+    let sweep = sweep.to_radians();
+    -PI / 2.0 - sweep / 2.0 + t.clamp(0.0, 1.0) * sweep
+}
+
+enum KnobEvent {
+    CancelTextInput,
+    TextInput(String),
+}
+
+impl View for ParamKnob {
+    fn element(&self) -> Option<&'static str> {
+        Some("paramknob")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if self.context_menu.handle_event(cx, event) {
+            return;
+        }
+
+        event.map(|knob_event, meta| match knob_event {
+            KnobEvent::CancelTextInput => {
+                self.text_input_active = false;
+                cx.set_active(false);
+
+                meta.consume();
+            }
+            KnobEvent::TextInput(string) => {
+                if let Some(normalized_value) = self.param_base.string_to_normalized_value(string) {
+                    self.param_base.begin_set_parameter(cx);
+                    self.param_base.set_normalized_value(cx, normalized_value);
+                    self.param_base.end_set_parameter(cx);
+                }
+
+                self.text_input_active = false;
+
+                meta.consume();
+            }
+        });
+
+        event.map(|window_event: &WindowEvent, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left)
+            | WindowEvent::MouseTripleClick(MouseButton::Left) => {
+                if cx.modifiers().command() {
+                    self.param_base.begin_set_parameter(cx);
+                    self.param_base
+                        .set_normalized_value(cx, self.param_base.default_normalized_value());
+                    self.param_base.end_set_parameter(cx);
+                } else if !self.text_input_active {
+                    cx.capture();
+                    cx.focus();
+                    cx.set_active(true);
+
+                    self.param_base.begin_set_parameter(cx);
+                    self.start_drag(cx.mouse().cursory, cx.modifiers().shift());
+                }
+            }
+            WindowEvent::MouseDoubleClick(MouseButton::Left) => {
+                if cfg!(not(windows)) {
+                    self.text_input_active = true;
+                }
+            }
+            WindowEvent::MouseMove(_x, y) => {
+                if let Some(drag_status) = self.drag_status {
+                    // Pressing or releasing shift mid-drag should not make the value jump
+                    let granular = cx.modifiers().shift();
+                    if granular != drag_status.granular {
+                        self.start_drag(*y, granular);
+                        return;
+                    }
+
+                    let multiplier = if granular {
+                        GRANULAR_DRAG_MULTIPLIER
+                    } else {
+                        1.0
+                    };
+                    // The distance should be compensated for the DPI scale so it remains
+                    // consistent
+                    let delta_y = (drag_status.starting_y_coordinate - *y)
+                        / (DRAG_DISTANCE * cx.scale_factor())
+                        * multiplier;
+
+                    self.param_base.set_normalized_value(
+                        cx,
+                        (drag_status.starting_value + delta_y).clamp(0.0, 1.0),
+                    );
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag_status.take().is_some() {
+                    cx.release();
+                    cx.set_active(false);
+
+                    self.param_base.end_set_parameter(cx);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseScroll(_scroll_x, scroll_y) => {
+                // With a regular scroll wheel `scroll_y` will only ever be -1 or 1, but with smooth
+                // scrolling trackpads being a thing `scroll_y` could be anything.
+                self.scrolled_lines += scroll_y;
+
+                if self.scrolled_lines.abs() >= 1.0 {
+                    let use_finer_steps = cx.modifiers().shift();
+                    let dragging = self.drag_status.is_some();
+
+                    // Scrolling while dragging needs to be taken into account here
+                    if !dragging {
+                        self.param_base.begin_set_parameter(cx);
+                    }
+
+                    let mut current_value = self.param_base.unmodulated_normalized_value();
+
+                    while self.scrolled_lines >= 1.0 {
+                        current_value = self
+                            .param_base
+                            .next_normalized_step(current_value, use_finer_steps);
+                        self.param_base.set_normalized_value(cx, current_value);
+                        self.scrolled_lines -= 1.0;
+                    }
+
+                    while self.scrolled_lines <= -1.0 {
+                        current_value = self
+                            .param_base
+                            .previous_normalized_step(current_value, use_finer_steps);
+                        self.param_base.set_normalized_value(cx, current_value);
+                        self.scrolled_lines += 1.0;
+                    }
+
+                    if !dragging {
+                        self.param_base.end_set_parameter(cx);
+                    } else {
+                        // Otherwise the next mouse move would undo the scroll
+                        self.start_drag(cx.mouse().cursory, use_finer_steps);
+                    }
+                }
+
+                meta.consume();
+            }
+            WindowEvent::KeyDown(Code::ArrowRight, _) | WindowEvent::KeyDown(Code::ArrowUp, _) => {
+                if self.text_input_active {
+                    return;
+                }
+
+                self.param_base.begin_set_parameter(cx);
+                let current_value = self.param_base.unmodulated_normalized_value();
+
+                let current_value = self
+                    .param_base
+                    .next_normalized_step(current_value, cx.modifiers().contains(Modifiers::SHIFT));
+                self.param_base.set_normalized_value(cx, current_value);
+
+                self.param_base.end_set_parameter(cx);
+            }
+            WindowEvent::KeyDown(Code::ArrowLeft, _) | WindowEvent::KeyDown(Code::ArrowDown, _) => {
+                if self.text_input_active {
+                    return;
+                }
+
+                self.param_base.begin_set_parameter(cx);
+                let current_value = self.param_base.unmodulated_normalized_value();
+
+                let current_value = self.param_base.previous_normalized_step(
+                    current_value,
+                    cx.modifiers().contains(Modifiers::SHIFT),
+                );
+                self.param_base.set_normalized_value(cx, current_value);
+
+                self.param_base.end_set_parameter(cx);
+            }
+            WindowEvent::KeyDown(Code::Enter, _) => {
+                self.text_input_active = true;
+            }
+            _ => {}
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum KnobArcKind {
+    /// The entire arc, drawn with the `background-color`.
+    Track,
+    /// The arc between the default value and the current value.
+    Fill,
+    /// The arc between the unmodulated and the modulated value, drawn just inside of the track.
+    Modulation,
+    /// A line from the center of the knob towards the current value.
+    Head,
+    /// Tick marks along the outer edge of this layer, as `(pos, short)` pairs. The layer is meant
+    /// to be slightly larger than the others so the ticks end up just outside of the track.
+    Ticks(Vec<(f32, bool)>),
+}
+
+/// One layer of a [`ParamKnob`]. The layers are stacked on top of each other so each of them can
+/// be styled separately. Apart from the track, all layers are drawn with the `color` property, and
+/// the `border-width` property sets the line width.
+struct KnobArc<V, M, S> {
+    kind: KnobArcKind,
+    value: V,
+    modulated_value: M,
+    default_value: f32,
+    sweep: S,
+}
+
+impl<V, M, S> View for KnobArc<V, M, S>
+where
+    V: Lens<Target = f32>,
+    M: Lens<Target = f32>,
+    S: Lens<Target = f32>,
+{
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let sweep = self.sweep.get(cx);
+        let value = self.value.get(cx);
+        let line_width = cx.border_width().max(cx.scale_factor());
+        let center_x = bounds.x + bounds.w / 2.0;
+        let center_y = bounds.y + bounds.h / 2.0;
+        let radius = (bounds.w.min(bounds.h) - line_width) / 2.0;
+
+        let mut paint = vg::Paint::color(match self.kind {
+            KnobArcKind::Track => cx.background_color().into(),
+            _ => cx.font_color().into(),
+        });
+        paint.set_line_width(line_width);
+
+        let arc = |radius: f32, from: f32, to: f32| {
+            let mut path = vg::Path::new();
+            let (from, to) = (from.min(to), from.max(to));
+            path.arc(
+                center_x,
+                center_y,
+                radius,
+                knob_angle(sweep, from),
+                knob_angle(sweep, to),
+                vg::Solidity::Hole,
+            );
+            path
+        };
+
+        match &self.kind {
+            KnobArcKind::Track => {
+                canvas.stroke_path(&arc(radius, 0.0, 1.0), &paint);
+            }
+            KnobArcKind::Fill => {
+                // Don't draw the filled portion at all if it could have been a rounding error
+                // since those slivers just look weird
+                if (value - self.default_value).abs() >= 1e-3 {
+                    canvas.stroke_path(&arc(radius, self.default_value, value), &paint);
+                }
+            }
+            KnobArcKind::Modulation => {
+                let modulated_value = self.modulated_value.get(cx);
+                if (modulated_value - value).abs() >= 1e-3 {
+                    let radius = radius - line_width * 2.0;
+                    canvas.stroke_path(&arc(radius, value, modulated_value), &paint);
+                }
+            }
+            KnobArcKind::Head => {
+                let angle = knob_angle(sweep, value);
+                let mut path = vg::Path::new();
+                path.move_to(
+                    center_x + angle.cos() * radius * 0.4,
+                    center_y + angle.sin() * radius * 0.4,
+                );
+                path.line_to(
+                    center_x + angle.cos() * radius,
+                    center_y + angle.sin() * radius,
+                );
+                canvas.stroke_path(&path, &paint);
+            }
+            KnobArcKind::Ticks(ticks) => {
+                let mut path = vg::Path::new();
+                for (pos, short) in ticks {
+                    let angle = knob_angle(sweep, *pos);
+                    let outer = radius + line_width / 2.0;
+                    let inner = outer - cx.scale_factor() * if *short { 3.0 } else { 4.0 };
+                    path.move_to(
+                        center_x + angle.cos() * inner,
+                        center_y + angle.sin() * inner,
+                    );
+                    path.line_to(
+                        center_x + angle.cos() * outer,
+                        center_y + angle.sin() * outer,
+                    );
+                }
+                canvas.stroke_path(&path, &paint);
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use nih_plug::prelude::*;
+use nih_plug_vizia::{vizia::prelude::*, widgets::RawParamEvent};
+
+use crate::{
+    midi_learn::{MidiLearnEvent, MidiLearnStatus, MidiLearnStatusRequest},
+    status_bar::ParamHoverEvent,
+};
+
+/// An entry a plugin adds to the [`ParamContextMenu`], below the built-in ones. The action gets
+/// the parameter the menu was opened for.
+pub struct ParamMenuEntry {
+    label: String,
+    action: Box<dyn Fn(&mut EventContext, ParamPtr)>,
+}
+
+impl ParamMenuEntry {
+    pub fn new(
+        label: impl Into<String>,
+        action: impl Fn(&mut EventContext, ParamPtr) + 'static,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            action: Box::new(action),
+        }
+    }
+}
+
+/// The state of the [`ParamContextMenu`], which is built at the root along with it.
+#[derive(Lens)]
+pub struct ParamMenu {
+    pub open: bool,
+    /// Where the menu was opened, in logical pixels from the top left of the window.
+    pub x: f32,
+    pub y: f32,
+    /// Whether the widget the menu was opened for is locked.
+    pub locked: bool,
+    /// Whether the menu shows a textbox for entering a value instead of its entries.
+    pub entering_value: bool,
+    /// The parameter's value when the menu was opened, formatted with its unit.
+    pub value_text: String,
+
+    #[lens(ignore)]
+    target: Option<(Entity, ParamPtr)>,
+    #[lens(ignore)]
+    entries: Vec<ParamMenuEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ParamMenuEvent {
+    /// Open the menu at the cursor for a param widget. Widgets emit this on right-click through
+    /// their [`ParamMenuTarget`].
+    Open {
+        target: Entity,
+        param: ParamPtr,
+        locked: bool,
+    },
+    Close,
+    ResetToDefault,
+    /// Show a textbox for entering a value.
+    EnterValue,
+    /// Set the parameter to a value entered as text.
+    SubmitValue(String),
+    CopyValue,
+    PasteValue,
+    ToggleLock,
+    /// Run the plugin's entry with this index.
+    Entry(usize),
+}
+
+/// Sent to a param widget by the [`ParamContextMenu`] to lock or unlock it.
+pub enum ParamLockEvent {
+    SetLocked(bool),
+}
+
+impl ParamMenu {
+    /// Set the parameter as a gesture, through the same events the param widgets use.
+    fn set(cx: &mut EventContext, param: ParamPtr, normalized_value: f32) {
+        cx.emit(RawParamEvent::BeginSetParameter(param));
+        cx.emit(RawParamEvent::SetParameterNormalized(
+            param,
+            normalized_value,
+        ));
+        cx.emit(RawParamEvent::EndSetParameter(param));
+    }
+
+    /// Parse `text` with the parameter's own string conversion, and set it if that worked.
+    fn set_from_string(cx: &mut EventContext, param: ParamPtr, text: &str) {
+        if let Some(normalized_value) = unsafe { param.string_to_normalized_value(text.trim()) } {
+            Self::set(cx, param, normalized_value);
+        }
+    }
+}
+
+impl Model for ParamMenu {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|menu_event, meta| {
+            meta.consume();
+
+            if let ParamMenuEvent::Open {
+                target,
+                param,
+                locked,
+            } = menu_event
+            {
+                self.target = Some((*target, *param));
+                self.open = true;
+                self.x = cx.mouse().cursorx / cx.scale_factor();
+                self.y = cx.mouse().cursory / cx.scale_factor();
+                self.locked = *locked;
+                self.entering_value = false;
+                self.value_text = unsafe {
+                    param.normalized_value_to_string(param.unmodulated_normalized_value(), true)
+                };
+
+                return;
+            }
+
+            let Some((target, param)) = self.target else {
+                return;
+            };
+
+            match menu_event {
+                ParamMenuEvent::Open { .. } | ParamMenuEvent::Close => {}
+                ParamMenuEvent::ResetToDefault => {
+                    Self::set(cx, param, unsafe { param.default_normalized_value() })
+                }
+                ParamMenuEvent::EnterValue => {
+                    // Keep the menu open to show the textbox
+                    self.entering_value = true;
+                    return;
+                }
+                ParamMenuEvent::SubmitValue(text) => Self::set_from_string(cx, param, text),
+                ParamMenuEvent::CopyValue => {
+                    // There's nothing to be done when the clipboard isn't available
+                    let _ = cx.set_clipboard(self.value_text.clone());
+                }
+                ParamMenuEvent::PasteValue => {
+                    if let Ok(text) = cx.get_clipboard() {
+                        Self::set_from_string(cx, param, &text);
+                    }
+                }
+                ParamMenuEvent::ToggleLock => {
+                    cx.emit_to(target, ParamLockEvent::SetLocked(!self.locked))
+                }
+                ParamMenuEvent::Entry(i) => {
+                    if let Some(entry) = self.entries.get(*i) {
+                        (entry.action)(cx, param);
+                    }
+                }
+            }
+
+            self.open = false;
+            self.entering_value = false;
+        });
+    }
+}
+
+/// The right-click menu of [`ParamSlider`][crate::param_slider::ParamSlider],
+/// [`ParamKnob`][crate::param_knob::ParamKnob], [`ParamSwitch`][crate::param_switch::ParamSwitch],
+/// [`ParamSelector`][crate::param_selector::ParamSelector] and
+/// [`ParamDropdown`][crate::param_dropdown::ParamDropdown]. It's shared by every widget, so it's
+/// built once at the root of the editor, after everything else. Without it, right-clicks don't do
+/// anything.
+///
+/// Locking a widget makes it ignore everything but right-clicks, and gives it the `locked` class.
+///
+/// ```ignore
+/// ParamContextMenu::with_entries(
+///     cx,
+///     vec![ParamMenuEntry::new("MIDI LEARN", |cx, param| cx.emit(MidiLearnEvent::Learn(param)))],
+/// );
+/// ```
+pub struct ParamContextMenu;
+
+impl ParamContextMenu {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self::with_entries(cx, Vec::new())
+    }
+
+    /// The same as [`ParamContextMenu::new()`], with extra entries at the bottom of the menu.
+    pub fn with_entries(cx: &mut Context, entries: Vec<ParamMenuEntry>) -> Handle<Self> {
+        let labels: Vec<String> = entries.iter().map(|entry| entry.label.clone()).collect();
+
+        ParamMenu {
+            open: false,
+            x: 0.0,
+            y: 0.0,
+            locked: false,
+            entering_value: false,
+            value_text: String::new(),
+            target: None,
+            entries,
+        }
+        .build(cx);
+
+        Self.build(cx, move |cx| {
+            Popup::new(cx, ParamMenu::open, true, move |cx| {
+                let labels = labels.clone();
+                Binding::new(cx, ParamMenu::entering_value, move |cx, entering_value| {
+                    if entering_value.get(cx) {
+                        Textbox::new(cx, ParamMenu::value_text)
+                            .on_submit(|cx, text, success| {
+                                if success {
+                                    cx.emit(ParamMenuEvent::SubmitValue(text));
+                                }
+                            })
+                            .on_cancel(|cx| cx.emit(ParamMenuEvent::Close))
+                            .on_build(|cx| {
+                                cx.emit(TextEvent::StartEdit);
+                                cx.emit(TextEvent::SelectAll);
+                            });
+
+                        return;
+                    }
+
+                    entry(cx, "RESET TO DEFAULT", ParamMenuEvent::ResetToDefault);
+                    entry(cx, "ENTER VALUE...", ParamMenuEvent::EnterValue);
+                    entry(cx, "COPY VALUE", ParamMenuEvent::CopyValue);
+                    entry(cx, "PASTE VALUE", ParamMenuEvent::PasteValue);
+                    Label::new(
+                        cx,
+                        ParamMenu::locked.map(|locked| if *locked { "UNLOCK" } else { "LOCK" }),
+                    )
+                    .on_press(|cx| cx.emit(ParamMenuEvent::ToggleLock))
+                    .class("option");
+
+                    if !labels.is_empty() {
+                        Element::new(cx).class("separator");
+                    }
+                    for (i, label) in labels.iter().enumerate() {
+                        Label::new(cx, label.as_str())
+                            .on_press(move |cx| cx.emit(ParamMenuEvent::Entry(i)))
+                            .class("option");
+                    }
+                });
+            })
+            .on_blur(|cx| cx.emit(ParamMenuEvent::Close))
+            .left(ParamMenu::x.map(|x| Pixels(*x)))
+            .top(ParamMenu::y.map(|y| Pixels(*y)));
+        })
+    }
+}
+
+impl View for ParamContextMenu {
+    fn element(&self) -> Option<&'static str> {
+        Some("paramcontextmenu")
+    }
+}
+
+fn entry(cx: &mut Context, label: &str, event: ParamMenuEvent) {
+    Label::new(cx, label)
+        .on_press(move |cx| cx.emit(event.clone()))
+        .class("option");
+}
+
+/// What a param widget needs to open the [`ParamContextMenu`], be locked from it, show its
+/// [`MidiLearner`][crate::midi_learn::MidiLearner] status, and be shown in the
+/// [`StatusBar`][crate::status_bar::StatusBar] when it's hovered. Widgets pass their events
+/// through [`ParamMenuTarget::handle_event()`] first.
+pub struct ParamMenuTarget {
+    param: ParamPtr,
+    locked: bool,
+}
+
+impl ParamMenuTarget {
+    pub fn new<L, Params, P, FMap>(cx: &mut Context, params: L, params_to_param: FMap) -> Self
+    where
+        L: Lens<Target = Params>,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        // Widgets that are built after the MIDI learn status last changed need to catch up
+        cx.emit(MidiLearnStatusRequest);
+
+        Self {
+            param: params
+                .map(move |params| params_to_param(params).as_ptr())
+                .get(cx),
+            locked: false,
+        }
+    }
+
+    /// Open the menu on right-click, start MIDI learn on alt-click, report hovers, and apply
+    /// [`ParamLockEvent`]s and MIDI learn status changes. Returns whether the widget should ignore
+    /// the event, either because it was handled here or because the widget is locked.
+    pub fn handle_event(&mut self, cx: &mut EventContext, event: &mut Event) -> bool {
+        let mut handled = false;
+
+        event.map(|window_event: &WindowEvent, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Right) => {
+                cx.emit(ParamMenuEvent::Open {
+                    target: cx.current(),
+                    param: self.param,
+                    locked: self.locked,
+                });
+
+                handled = true;
+                meta.consume();
+            }
+            WindowEvent::MouseDown(MouseButton::Left) if cx.modifiers().alt() => {
+                cx.emit(MidiLearnEvent::Learn(self.param));
+
+                handled = true;
+                meta.consume();
+            }
+            WindowEvent::MouseMove(..) => cx.emit(ParamHoverEvent(self.param)),
+            _ => {}
+        });
+
+        event.map(|lock_event, meta| match lock_event {
+            ParamLockEvent::SetLocked(locked) => {
+                self.locked = *locked;
+                cx.toggle_class("locked", *locked);
+                meta.consume();
+            }
+        });
+
+        event.map(|status: &MidiLearnStatus, _| {
+            cx.toggle_class("learning", status.learning == Some(self.param));
+            cx.toggle_class("mapped", status.mapped.contains(&self.param));
+        });
+
+        handled || self.locked
+    }
+}
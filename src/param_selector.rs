@@ -1,13 +1,24 @@
 use nih_plug::prelude::*;
 use nih_plug_vizia::{vizia::prelude::*, widgets::param_base::ParamWidgetBase};
 
+use crate::{
+    orientation::Orientation,
+    param_menu::ParamMenuTarget,
+    param_value,
+    selector::{self, SelectorKey},
+};
+
 enum SelectorEvent {
     SetTo(f32),
 }
 
+/// A button for every step of a discrete parameter. Like [`Selector`][crate::selector::Selector],
+/// it's focused as a whole and its selection can be moved with the arrow keys and Home and End.
 #[derive(Lens)]
 pub struct ParamSelector {
     param_base: ParamWidgetBase,
+    context_menu: ParamMenuTarget,
+    orientation: Orientation,
 }
 
 impl ParamSelector {
@@ -23,7 +34,9 @@ impl ParamSelector {
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
         Self {
+            context_menu: ParamMenuTarget::new(cx, params.clone(), params_to_param),
             param_base: ParamWidgetBase::new(cx, params, params_to_param),
+            orientation: Orientation::default(),
         }
         .build(
             cx,
@@ -36,13 +49,44 @@ impl ParamSelector {
                             .on_press(move |cx| cx.emit(SelectorEvent::SetTo(value)))
                             .toggle_class(
                                 "on",
+                                param_data.make_lens(move |p| param_value::is_selected(p, value)),
+                            )
+                            .toggle_class(
+                                "modulated",
                                 param_data
-                                    .make_lens(move |p| p.modulated_normalized_value() == value),
-                            );
+                                    .make_lens(move |p| param_value::is_modulated_to(p, value)),
+                            )
+                            .navigable(false);
                     }
-                });
+                })
+                .layout_type(
+                    ParamSelector::orientation
+                        .map(|orientation| selector::layout_type(*orientation)),
+                );
             }),
         )
+        .toggle_class(
+            "vertical",
+            ParamSelector::orientation.map(|orientation| *orientation == Orientation::Vertical),
+        )
+        .navigable(true)
+    }
+
+    fn set(&self, cx: &mut EventContext, normalized_value: f32) {
+        self.param_base.begin_set_parameter(cx);
+        self.param_base.set_normalized_value(cx, normalized_value);
+        self.param_base.end_set_parameter(cx);
+    }
+}
+
+pub trait ParamSelectorModifiers {
+    /// Lay the buttons out in a row (the default) or in a column.
+    fn orientation(self, orientation: Orientation) -> Self;
+}
+
+impl ParamSelectorModifiers for Handle<'_, ParamSelector> {
+    fn orientation(self, orientation: Orientation) -> Self {
+        self.modify(|selector| selector.orientation = orientation)
     }
 }
 
@@ -51,11 +95,36 @@ impl View for ParamSelector {
         Some("selector")
     }
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if self.context_menu.handle_event(cx, event) {
+            return;
+        }
+
         event.map(|param_slider_event, meta| match param_slider_event {
             SelectorEvent::SetTo(x) => {
-                self.param_base.begin_set_parameter(cx);
-                self.param_base.set_normalized_value(cx, *x);
-                self.param_base.end_set_parameter(cx);
+                cx.focus();
+                self.set(cx, *x);
+                meta.consume();
+            }
+        });
+
+        event.map(|window_event: &WindowEvent, meta| {
+            if let WindowEvent::KeyDown(code, _) = window_event {
+                let Some(key) = SelectorKey::from_code(*code, self.orientation) else {
+                    return;
+                };
+                let current_value = self.param_base.unmodulated_normalized_value();
+                let value = match key {
+                    SelectorKey::Previous => self
+                        .param_base
+                        .previous_normalized_step(current_value, false),
+                    SelectorKey::Next => self.param_base.next_normalized_step(current_value, false),
+                    SelectorKey::First => 0.0,
+                    SelectorKey::Last => 1.0,
+                };
+
+                if value != current_value {
+                    self.set(cx, value);
+                }
                 meta.consume();
             }
         });
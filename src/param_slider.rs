@@ -4,24 +4,56 @@ use nih_plug_vizia::{
     widgets::{param_base::ParamWidgetBase, util::*},
 };
 
+use crate::{orientation::Orientation, param_menu::ParamMenuTarget};
+
 #[derive(Lens)]
 pub struct ParamSlider {
     param_base: ParamWidgetBase,
+    context_menu: ParamMenuTarget,
     dragging: bool,
     granular_drag_status: Option<GranularDragStatus>,
     pub text_input_active: bool,
     scrolled_lines: f32,
+    style: SliderStyle,
+    modulation_head: bool,
+    orientation: Orientation,
+    tick_placement: TickPlacement,
+    /// The track's entity, used to map the mouse's position to a value for vertical sliders since
+    /// the name and value labels are stacked on top of the track.
+    track: Option<Entity>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+/// How the fill region of a [`ParamSlider`] is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
 pub enum SliderStyle {
+    /// Fill from the default value if it's in the center of the range, otherwise from the left.
+    #[default]
     Centered,
+    /// Always fill from the left.
     FromLeft,
+    /// Always fill from the center of the range.
     FromMidPoint,
+    /// Only highlight the current step. With `even`, every step takes up the same amount of space
+    /// on the slider.
     CurrentStep { even: bool },
+    /// The same as `CurrentStep`, but with the label of every step drawn inside of the track.
     CurrentStepLabeled { even: bool },
 }
 
+/// The name [`Orientation`] had when only sliders had one.
+pub use crate::orientation::Orientation as SliderOrientation;
+
+/// Which side of the track a [`ParamSlider`]'s ticks are drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
+pub enum TickPlacement {
+    /// Above a horizontal slider, or to the left of a vertical one.
+    Before,
+    /// Below a horizontal slider, or to the right of a vertical one.
+    #[default]
+    After,
+}
+
+#[derive(Debug, Clone)]
 pub struct SliderTick {
     pub pos: f32,
     pub label: Option<String>,
@@ -30,11 +62,128 @@ pub struct SliderTick {
 
 #[derive(Debug, Clone, Copy)]
 pub struct GranularDragStatus {
-    pub starting_x_coordinate: f32,
+    /// The x-coordinate for horizontal sliders, or the y-coordinate for vertical ones.
+    pub starting_coordinate: f32,
     pub starting_value: f32,
 }
 const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
 
+/// Position an element along the track, starting at the normalized position `start` and spanning
+/// `length`. Vertical tracks start at the bottom.
+fn along_track<'a, V: View>(
+    handle: Handle<'a, V>,
+    vertical: bool,
+    start: impl Lens<Target = f32>,
+    length: impl Lens<Target = f32>,
+) -> Handle<'a, V> {
+    if vertical {
+        handle
+            .width(Stretch(1.0))
+            .top(Stretch(1.0))
+            .bottom(start.map(|start_t| Percentage(start_t * 100.0)))
+            .height(length.map(|delta| Percentage(delta * 100.0)))
+    } else {
+        handle
+            .height(Stretch(1.0))
+            .left(start.map(|start_t| Percentage(start_t * 100.0)))
+            .width(length.map(|delta| Percentage(delta * 100.0)))
+    }
+}
+
+/// A one pixel wide line across the track at the normalized position `position`.
+fn head(cx: &mut Context, vertical: bool, position: impl Lens<Target = f32>) -> Handle<Element> {
+    let handle = Element::new(cx);
+    let handle = if vertical {
+        handle
+            .width(Stretch(1.0))
+            .height(Pixels(1.0))
+            .top(Stretch(1.0))
+            .bottom(position.map(|x| Percentage(x * 100.0)))
+    } else {
+        handle
+            .height(Stretch(1.0))
+            .width(Pixels(1.0))
+            .left(position.map(|x| Percentage(x * 100.0)))
+    };
+
+    handle.translate(nudge(vertical, -0.5)).class("head")
+}
+
+/// Draw tick marks and their labels next to a track. `before` places them above a horizontal track
+/// or to the left of a vertical one, in which case the labels go on the far side of the ticks.
+pub(crate) fn tick_marks(cx: &mut Context, ticks: &[SliderTick], vertical: bool, before: bool) {
+    if ticks.is_empty() {
+        return;
+    }
+
+    fn tickmark<'a>(cx: &'a mut Context, tick_short: &bool) -> Handle<'a, Element> {
+        Element::new(cx)
+            .class("tick")
+            .toggle_class("short", *tick_short)
+    }
+
+    ZStack::new(cx, |cx| {
+        for tick in ticks {
+            if let Some(label) = tick.label.as_ref() {
+                let content = move |cx: &mut Context| {
+                    let label = |cx: &mut Context| {
+                        if vertical {
+                            Label::new(cx, label)
+                                .class("tick-label")
+                                .height(Pixels(1.0))
+                                .child_top(Stretch(1.0))
+                                .child_bottom(Stretch(1.0));
+                        } else {
+                            Label::new(cx, label)
+                                .class("tick-label")
+                                .width(Pixels(1.0))
+                                .text_align(TextAlign::Center);
+                        }
+                    };
+
+                    if before {
+                        label(cx);
+                        tickmark(cx, &tick.short);
+                    } else {
+                        tickmark(cx, &tick.short);
+                        label(cx);
+                    }
+                };
+
+                if vertical {
+                    HStack::new(cx, content)
+                        .height(Pixels(1.0))
+                        .width(Auto)
+                        .top(Stretch(1.0))
+                        .bottom(Units::Percentage(tick.pos * 100.0));
+                } else {
+                    VStack::new(cx, content)
+                        .width(Pixels(1.0))
+                        .height(Auto)
+                        .left(Units::Percentage(tick.pos * 100.0));
+                }
+            } else if vertical {
+                tickmark(cx, &tick.short)
+                    .top(Stretch(1.0))
+                    .bottom(Units::Percentage(tick.pos * 100.0));
+            } else {
+                tickmark(cx, &tick.short).left(Units::Percentage(tick.pos * 100.0));
+            }
+        }
+    })
+    .class("ticks")
+    .toggle_class("before", before);
+}
+
+/// Shift an element by `amount` pixels towards the end of the track.
+fn nudge(vertical: bool, amount: f32) -> Translate {
+    if vertical {
+        Translate::new(Pixels(0.0), Pixels(-amount))
+    } else {
+        Translate::new(Pixels(amount), Pixels(0.0))
+    }
+}
+
 impl ParamSlider {
     pub fn new<L, Params, P, FMap>(
         cx: &mut Context,
@@ -48,14 +197,21 @@ impl ParamSlider {
         P: Param + 'static,
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
-        let style = SliderStyle::Centered;
+        // The layout is rebuilt when the orientation changes, so the ticks need to stick around
+        let ticks: Vec<SliderTick> = ticks.into_iter().collect();
 
         Self {
+            context_menu: ParamMenuTarget::new(cx, params.clone(), params_to_param),
             param_base: ParamWidgetBase::new(cx, params, params_to_param),
             dragging: false,
             granular_drag_status: None,
             text_input_active: false,
             scrolled_lines: 0.0,
+            style: SliderStyle::default(),
+            modulation_head: true,
+            orientation: Orientation::default(),
+            tick_placement: TickPlacement::default(),
+            track: None,
         }
         .build(
             cx,
@@ -66,17 +222,7 @@ impl ParamSlider {
                     param.normalized_value_to_string(param.unmodulated_normalized_value(), true)
                 });
 
-                let fill_start_delta_lens =
-                    unmodulated_normalized_value_lens.map(move |current_value| {
-                        Self::compute_fill_start_delta(style, param_data.param(), *current_value)
-                    });
-
-                // let modulation_start_delta_lens = param_data.make_lens(move |param| {
-                //     Self::compute_modulation_fill_start_delta(style, param)
-                // });
-
-                HStack::new(cx, |cx| {
-                    Label::new(cx, param_data.param().name().to_uppercase().as_str()).class("name");
+                let value_display = move |cx: &mut Context| {
                     Binding::new(
                         cx,
                         ParamSlider::text_input_active,
@@ -109,71 +255,198 @@ impl ParamSlider {
                             }
                         },
                     );
-                })
-                .class("title");
-
-                ZStack::new(cx, |cx| {
-                    ZStack::new(cx, |cx| {
-                        Element::new(cx)
-                            .height(Stretch(1.0))
-                            .left(
-                                fill_start_delta_lens
-                                    .map(|(start_t, _)| Percentage(start_t * 100.0)),
-                            )
-                            .width(
-                                fill_start_delta_lens.map(|(_, delta)| Percentage(delta * 100.0)),
-                            )
-                            .translate(Translate::new(Pixels(0.5), Pixels(0.0)))
-                            .class("slider")
-                            .hoverable(false);
-                        Element::new(cx)
-                            .height(Stretch(1.0))
-                            .width(Pixels(1.0))
-                            .translate(Translate::new(Pixels(-0.5), Pixels(0.0)))
-                            .left(unmodulated_normalized_value_lens.map(|x| Percentage(x * 100.0)))
-                            .class("head");
-                    })
-                    .overflow(Overflow::Hidden);
-                })
-                .class("track");
-
-                let mut ticks = ticks.into_iter().peekable();
-
-                if ticks.peek().is_some() {
-                    ZStack::new(cx, |cx| {
-                        for tick in ticks {
-                            fn tickmark<'a>(
-                                cx: &'a mut Context,
-                                tick_short: &bool,
-                            ) -> Handle<'a, Element> {
-                                Element::new(cx)
-                                    .class("tick")
-                                    .toggle_class("short", *tick_short)
-                            }
+                };
+
+                let track = move |cx: &mut Context, vertical: bool| {
+                    Binding::new(cx, ParamSlider::style, move |cx, style| {
+                        let style = style.get(cx);
+                        let fill_start_delta_lens =
+                            unmodulated_normalized_value_lens.map(move |current_value| {
+                                Self::compute_fill_start_delta(
+                                    style,
+                                    param_data.param(),
+                                    *current_value,
+                                )
+                            });
+                        let modulation_start_delta_lens = param_data.make_lens(move |param| {
+                            Self::compute_modulation_fill_start_delta(style, param)
+                        });
+
+                        ZStack::new(cx, |cx| {
+                            ZStack::new(cx, |cx| {
+                                along_track(
+                                    Element::new(cx),
+                                    vertical,
+                                    fill_start_delta_lens.map(|(start_t, _)| *start_t),
+                                    fill_start_delta_lens.map(|(_, delta)| *delta),
+                                )
+                                .translate(nudge(vertical, 0.5))
+                                .class("slider")
+                                .hoverable(false);
+
+                                // If the parameter is being modulated by the host (this only works
+                                // for CLAP plugins with hosts that support this), then this is the
+                                // difference between the unmodulated value under the user's mouse
+                                // and the value after modulation has been applied. Widths cannot
+                                // be negative, so the start needs to be moved back when modulating
+                                // downwards.
+                                along_track(
+                                    Element::new(cx),
+                                    vertical,
+                                    modulation_start_delta_lens
+                                        .map(|(start_t, delta)| start_t.min(start_t + delta)),
+                                    modulation_start_delta_lens.map(|(_, delta)| delta.abs()),
+                                )
+                                .visibility(
+                                    modulation_start_delta_lens
+                                        .map(|(_, delta)| delta.abs() >= 1e-3),
+                                )
+                                .translate(nudge(vertical, 0.5))
+                                .class("modulation")
+                                .hoverable(false);
+
+                                // The stepped styles highlight the entire step, so a head in the
+                                // middle of that step would only get in the way
+                                if !matches!(
+                                    style,
+                                    SliderStyle::CurrentStep { .. }
+                                        | SliderStyle::CurrentStepLabeled { .. }
+                                ) {
+                                    head(cx, vertical, unmodulated_normalized_value_lens);
+
+                                    Binding::new(
+                                        cx,
+                                        ParamSlider::modulation_head,
+                                        move |cx, modulation_head| {
+                                            if modulation_head.get(cx) {
+                                                head(
+                                                    cx,
+                                                    vertical,
+                                                    modulation_start_delta_lens
+                                                        .map(|(start_t, delta)| start_t + delta),
+                                                )
+                                                .visibility(
+                                                    modulation_start_delta_lens
+                                                        .map(|(_, delta)| delta.abs() >= 1e-3),
+                                                )
+                                                .class("modulation")
+                                                .hoverable(false);
+                                            }
+                                        },
+                                    );
+                                }
+
+                                if let SliderStyle::CurrentStepLabeled { even } = style {
+                                    Self::step_labels(cx, param_data.param(), even, vertical);
+                                }
+                            })
+                            .overflow(Overflow::Hidden);
+                        })
+                        .class("track")
+                        .on_build(|cx| cx.emit(SliderEvent::TrackBuilt(cx.current())));
+                    });
+                };
 
-                            if let Some(label) = tick.label.as_ref() {
-                                VStack::new(cx, move |cx| {
-                                    tickmark(cx, &tick.short);
-                                    Label::new(cx, label)
-                                        .class("tick-label")
-                                        .width(Pixels(1.0))
-                                        .text_align(TextAlign::Center);
+                Binding::new(cx, ParamSlider::orientation, move |cx, orientation| {
+                    let vertical = orientation.get(cx) == Orientation::Vertical;
+                    let ticks = ticks.clone();
+
+                    Binding::new(
+                        cx,
+                        ParamSlider::tick_placement,
+                        move |cx, tick_placement| {
+                            let before = tick_placement.get(cx) == TickPlacement::Before;
+                            let name = param_data.param().name().to_uppercase();
+
+                            if vertical {
+                                Label::new(cx, name.as_str()).class("name");
+                                HStack::new(cx, |cx| {
+                                    if before {
+                                        tick_marks(cx, &ticks, true, true);
+                                    }
+                                    track(cx, true);
+                                    if !before {
+                                        tick_marks(cx, &ticks, true, false);
+                                    }
                                 })
-                                .width(Pixels(1.0))
-                                .height(Auto)
-                                .left(Units::Percentage(tick.pos * 100.0));
+                                .class("body");
+                                value_display(cx);
                             } else {
-                                tickmark(cx, &tick.short).left(Units::Percentage(tick.pos * 100.0));
+                                HStack::new(cx, |cx| {
+                                    Label::new(cx, name.as_str()).class("name");
+                                    value_display(cx);
+                                })
+                                .class("title");
+
+                                if before {
+                                    tick_marks(cx, &ticks, false, true);
+                                }
+                                track(cx, false);
+                                if !before {
+                                    tick_marks(cx, &ticks, false, false);
+                                }
                             }
-                        }
-                    })
-                    .class("ticks");
-                }
+                        },
+                    );
+                });
             }),
         )
+        .toggle_class(
+            "vertical",
+            ParamSlider::orientation.map(|orientation| *orientation == Orientation::Vertical),
+        )
         .navigable(true)
     }
 
+    /// Draw the label of every step inside of the track for the `CurrentStepLabeled` style. With
+    /// `even`, every step gets the same amount of space. Otherwise the labels are centered on the
+    /// step's normalized value, the same way the fill is drawn.
+    fn step_labels<P: Param>(cx: &mut Context, param: &P, even: bool, vertical: bool) {
+        // A parameter with a single value doesn't have any steps to label
+        let Some(step_count) = param.step_count().filter(|step_count| *step_count > 0) else {
+            return;
+        };
+
+        // There are `step_count + 1` discrete values for a stepped parameter
+        let discrete_values = step_count as f32 + 1.0;
+
+        for step in 0..=step_count {
+            let normalized_value = step as f32 / step_count as f32;
+            let (start_t, delta) = if even {
+                (step as f32 / discrete_values, discrete_values.recip())
+            } else {
+                Self::compute_fill_start_delta(
+                    SliderStyle::CurrentStep { even: false },
+                    param,
+                    normalized_value,
+                )
+            };
+
+            let label = Label::new(
+                cx,
+                param
+                    .normalized_value_to_string(normalized_value, false)
+                    .as_str(),
+            )
+            .class("step-label")
+            .child_space(Stretch(1.0))
+            .hoverable(false);
+
+            if vertical {
+                label
+                    .top(Stretch(1.0))
+                    .bottom(Percentage(start_t * 100.0))
+                    .height(Percentage(delta * 100.0))
+                    .width(Stretch(1.0));
+            } else {
+                label
+                    .left(Percentage(start_t * 100.0))
+                    .width(Percentage(delta * 100.0))
+                    .height(Stretch(1.0));
+            }
+        }
+    }
+
     /// Calculate the start position and width of the slider's fill region based on the selected
     /// style, the parameter's current value, and the parameter's step sizes. The resulting tuple
     /// `(start_t, delta)` corresponds to the start and the signed width of the bar. `start_t` is in
@@ -235,7 +508,8 @@ impl ParamSlider {
         }
     }
 
-    /// The same as `compute_fill_start_delta`, but just showing the modulation offset.
+    /// The same as `compute_fill_start_delta`, but just showing the modulation offset. The
+    /// resulting `delta` is negative when the modulation moves the value downwards.
     fn compute_modulation_fill_start_delta<P: Param>(style: SliderStyle, param: &P) -> (f32, f32) {
         match style {
             // Don't show modulation for stepped parameters since it wouldn't
@@ -257,12 +531,110 @@ impl ParamSlider {
     /// to match up with the fill value display. This still needs to be wrapped in a parameter
     /// automation gesture.
     fn set_normalized_value_drag(&self, cx: &mut EventContext, normalized_value: f32) {
+        // A parameter with a single value has nothing to remap
+        let step_count = self
+            .param_base
+            .step_count()
+            .filter(|step_count| *step_count > 0);
+        let normalized_value = match (self.style, step_count) {
+            (
+                SliderStyle::CurrentStep { even: true }
+                | SliderStyle::CurrentStepLabeled { even: true },
+                Some(step_count),
+            ) => {
+                // Every step occupies an equal part of the slider, so the value range needs to be
+                // remapped so that each of those parts maps to its step instead of the steps'
+                // centers being spread over the entire `[0, 1]` range
+                let discrete_values = step_count as f32 + 1.0;
+                let rounded_value = ((normalized_value * discrete_values) - 0.5).round();
+
+                (rounded_value / step_count as f32).clamp(0.0, 1.0)
+            }
+            _ => normalized_value,
+        };
+
         self.param_base.set_normalized_value(cx, normalized_value);
     }
+
+    /// The mouse coordinate along the track's axis.
+    fn drag_coordinate(&self, x: f32, y: f32) -> f32 {
+        match self.orientation {
+            Orientation::Horizontal => x,
+            Orientation::Vertical => y,
+        }
+    }
+
+    /// Map a coordinate along the track's axis to a normalized value.
+    fn coordinate_to_value(&self, cx: &EventContext, coordinate: f32) -> f32 {
+        match (self.orientation, self.track) {
+            (Orientation::Horizontal, _) => remap_current_entity_x_coordinate(cx, coordinate),
+            (Orientation::Vertical, Some(track)) => {
+                let bounds = cx.cache.get_bounds(track);
+                if bounds.h <= 0.0 {
+                    return self.param_base.unmodulated_normalized_value();
+                }
+
+                // We're working with a flipped y-axis
+                (1.0 - (coordinate - bounds.y) / bounds.h).clamp(0.0, 1.0)
+            }
+            (Orientation::Vertical, None) => remap_current_entity_y_coordinate(cx, coordinate),
+        }
+    }
+
+    /// The inverse of [`Self::coordinate_to_value()`].
+    fn value_to_coordinate(&self, cx: &EventContext, value: f32) -> f32 {
+        match (self.orientation, self.track) {
+            (Orientation::Horizontal, _) => remap_current_entity_x_t(cx, value),
+            (Orientation::Vertical, Some(track)) => {
+                let bounds = cx.cache.get_bounds(track);
+                bounds.y + (1.0 - value) * bounds.h
+            }
+            (Orientation::Vertical, None) => remap_current_entity_y_t(cx, value),
+        }
+    }
 }
+
+pub trait ParamSliderModifiers {
+    /// Change how the slider's fill is drawn. The style is bound to the lens, so it can be changed
+    /// at runtime.
+    fn slider_style(self, style: impl Lens<Target = SliderStyle>) -> Self;
+
+    /// Whether to draw a second head at the modulated value when the host or CLAP polyphonic
+    /// modulation moves the parameter away from its unmodulated value. Enabled by default.
+    fn modulation_head(self, enabled: bool) -> Self;
+
+    /// Lay the slider out horizontally (the default) or vertically, like a channel fader.
+    fn orientation(self, orientation: Orientation) -> Self;
+
+    /// Draw the ticks on the other side of the track.
+    fn tick_placement(self, placement: TickPlacement) -> Self;
+}
+
+impl ParamSliderModifiers for Handle<'_, ParamSlider> {
+    fn slider_style(self, style: impl Lens<Target = SliderStyle>) -> Self {
+        self.bind(style, |mut handle, style| {
+            let style = style.get(&*handle.context());
+            handle.modify(|slider| slider.style = style);
+        })
+    }
+
+    fn modulation_head(self, enabled: bool) -> Self {
+        self.modify(|slider| slider.modulation_head = enabled)
+    }
+
+    fn orientation(self, orientation: Orientation) -> Self {
+        self.modify(|slider| slider.orientation = orientation)
+    }
+
+    fn tick_placement(self, placement: TickPlacement) -> Self {
+        self.modify(|slider| slider.tick_placement = placement)
+    }
+}
+
 enum SliderEvent {
     CancelTextInput,
     TextInput(String),
+    TrackBuilt(Entity),
 }
 
 impl View for ParamSlider {
@@ -271,6 +643,10 @@ impl View for ParamSlider {
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if self.context_menu.handle_event(cx, event) {
+            return;
+        }
+
         event.map(|param_slider_event, meta| match param_slider_event {
             SliderEvent::CancelTextInput => {
                 self.text_input_active = false;
@@ -287,6 +663,11 @@ impl View for ParamSlider {
 
                 self.text_input_active = false;
 
+                meta.consume();
+            }
+            SliderEvent::TrackBuilt(entity) => {
+                self.track = Some(*entity);
+
                 meta.consume();
             }
         });
@@ -310,7 +691,8 @@ impl View for ParamSlider {
                     self.param_base.begin_set_parameter(cx);
                     if cx.modifiers().shift() {
                         self.granular_drag_status = Some(GranularDragStatus {
-                            starting_x_coordinate: cx.mouse().cursorx,
+                            starting_coordinate: self
+                                .drag_coordinate(cx.mouse().cursorx, cx.mouse().cursory),
                             starting_value: self.param_base.unmodulated_normalized_value(),
                         });
                     } else {
@@ -323,8 +705,10 @@ impl View for ParamSlider {
                     self.text_input_active = true;
                 }
             }
-            WindowEvent::MouseMove(x, _y) => {
+            WindowEvent::MouseMove(x, y) => {
                 if self.dragging {
+                    let coordinate = self.drag_coordinate(*x, *y);
+
                     // If shift is being held then the drag should be more granular instead of
                     // absolute
                     if cx.modifiers().shift() {
@@ -332,27 +716,27 @@ impl View for ParamSlider {
                             *self
                                 .granular_drag_status
                                 .get_or_insert_with(|| GranularDragStatus {
-                                    starting_x_coordinate: *x,
+                                    starting_coordinate: coordinate,
                                     starting_value: self.param_base.unmodulated_normalized_value(),
                                 });
 
                         // These positions should be compensated for the DPI scale so it remains
                         // consistent
-                        let start_x =
-                            remap_current_entity_x_t(cx, granular_drag_status.starting_value);
-                        let delta_x = ((*x - granular_drag_status.starting_x_coordinate)
+                        let start =
+                            self.value_to_coordinate(cx, granular_drag_status.starting_value);
+                        let delta = ((coordinate - granular_drag_status.starting_coordinate)
                             * GRANULAR_DRAG_MULTIPLIER)
                             * cx.scale_factor();
 
                         self.set_normalized_value_drag(
                             cx,
-                            remap_current_entity_x_coordinate(cx, start_x + delta_x),
+                            self.coordinate_to_value(cx, start + delta),
                         );
                     } else {
                         self.granular_drag_status = None;
                         self.set_normalized_value_drag(
                             cx,
-                            remap_current_entity_x_coordinate(cx, *x),
+                            self.coordinate_to_value(cx, coordinate),
                         );
                     }
                 }
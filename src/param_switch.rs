@@ -1,20 +1,38 @@
 use nih_plug::prelude::*;
 use nih_plug_vizia::{
-    vizia::{icons::ICON_CHEVRON_DOWN, prelude::*},
-    widgets::param_base::ParamWidgetBase,
+    vizia::prelude::*,
+    widgets::param_base::{ParamWidgetBase, ParamWidgetData},
 };
 
-enum SelectorEvent {
-    SetTo(f32),
-}
+use crate::{param_menu::ParamMenuTarget, param_value};
 
+/// A switch with a position for every step of a parameter. [`BoolParam`]s get the familiar two
+/// positions, while [`IntParam`]s and [`EnumParam`]s get one for every value. Parameters without
+/// steps are treated as having two, which are only on at either end of the range.
+///
+/// Clicking the switch moves it to the next position, wrapping around at the end, and
+/// shift-clicking moves it back. Like [`ParamSlider`][crate::param_slider::ParamSlider], the
+/// scroll wheel and the arrow keys step through the positions without wrapping, and ctrl-clicking
+/// (cmd on macOS) resets the parameter to its default value. In momentary mode, the switch only
+/// moves while the mouse is held down, and goes back to the default value when it's released.
+///
+/// The positions get the `on` class when the parameter is on them, and the `modulated` class when
+/// modulation moves the parameter onto them.
 #[derive(Lens)]
 pub struct ParamSwitch {
     param_base: ParamWidgetBase,
-}
+    context_menu: ParamMenuTarget,
 
-enum ParamSwitchEvent {
-    Switch
+    /// The number of positions minus one, like a parameter's step count.
+    step_count: usize,
+    /// The text drawn inside each position.
+    labels: Vec<String>,
+    momentary: bool,
+    /// Whether the mouse is held down on a momentary switch.
+    pressed: bool,
+    /// The same as in [`ParamSlider`][crate::param_slider::ParamSlider], scrolling by less than a
+    /// line accumulates until it adds up to one.
+    scrolled_lines: f32,
 }
 
 impl ParamSwitch {
@@ -29,18 +47,125 @@ impl ParamSwitch {
         P: Param + 'static,
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
+        let step_count = params
+            .clone()
+            .map(move |params| switch_steps(params_to_param(params)))
+            .get(cx);
+
         Self {
-            param_base: ParamWidgetBase::new(cx, params, params_to_param),
+            context_menu: ParamMenuTarget::new(cx, params.clone(), params_to_param),
+            param_base: ParamWidgetBase::new(cx, params.clone(), params_to_param),
+            step_count,
+            labels: Vec::new(),
+            momentary: false,
+            pressed: false,
+            scrolled_lines: 0.0,
         }
         .build(
             cx,
             ParamWidgetBase::build_view(params, params_to_param, move |cx, param_data| {
-                Switch::new(
-                    cx,
-                    param_data.make_lens(|p| p.unmodulated_normalized_value() > 0.5)
-                ).on_toggle(|cx| cx.emit(ParamSwitchEvent::Switch));
+                Binding::new(cx, ParamSwitch::labels, move |cx, labels| {
+                    let labels = labels.get(cx);
+                    positions(cx, param_data, &labels);
+                });
             }),
         )
+        .toggle_class("momentary", ParamSwitch::momentary)
+        .navigable(true)
+    }
+
+    /// Set the parameter as a gesture of its own.
+    fn set(&self, cx: &mut EventContext, normalized_value: f32) {
+        self.param_base.begin_set_parameter(cx);
+        self.param_base.set_normalized_value(cx, normalized_value);
+        self.param_base.end_set_parameter(cx);
+    }
+
+    /// The normalized value of the position `delta` positions away from the current one. With
+    /// `wrap`, moving past the last position goes to the first one and the other way around.
+    /// Otherwise the switch stops at the ends.
+    fn step(&self, delta: isize, wrap: bool) -> f32 {
+        let positions = self.step_count as isize + 1;
+        let current = position(
+            self.param_base.unmodulated_normalized_value(),
+            self.step_count,
+        );
+        let target = if wrap {
+            (current as isize + delta).rem_euclid(positions)
+        } else {
+            (current as isize + delta).clamp(0, positions - 1)
+        };
+
+        target as f32 / self.step_count as f32
+    }
+}
+
+/// The number of steps of a parameter, counting a parameter without steps as a switch.
+fn switch_steps<P: Param>(param: &P) -> usize {
+    param.step_count().unwrap_or(1).max(1)
+}
+
+/// The position closest to a normalized value, which the switch moves from. Which position is shown
+/// as on is up to [`param_value`], like for the other param widgets.
+fn position(normalized_value: f32, step_count: usize) -> usize {
+    (normalized_value * step_count as f32).round() as usize
+}
+
+/// A label for every position of the switch, which doubles as the handle when it's on.
+fn positions<L, Params, P, FMap>(
+    cx: &mut Context,
+    param_data: ParamWidgetData<L, Params, P, FMap>,
+    labels: &[String],
+) where
+    L: Lens<Target = Params> + Clone,
+    Params: 'static,
+    P: Param + 'static,
+    FMap: Fn(&Params) -> &P + Copy + 'static,
+{
+    let step_count = switch_steps(param_data.param());
+
+    HStack::new(cx, |cx| {
+        for i in 0..=step_count {
+            let value = i as f32 / step_count as f32;
+
+            Label::new(cx, labels.get(i).map_or("", String::as_str))
+                .class("position")
+                .toggle_class(
+                    "on",
+                    param_data.make_lens(move |p| param_value::is_selected(p, value)),
+                )
+                .toggle_class(
+                    "modulated",
+                    param_data.make_lens(move |p| param_value::is_modulated_to(p, value)),
+                );
+        }
+    })
+    .class("switch-track")
+    .toggle_class("labeled", !labels.is_empty());
+}
+
+pub trait ParamSwitchModifiers {
+    /// Draw text inside the switch, one label for each position from the first to the last. A
+    /// [`BoolParam`]'s switch takes an off and an on label.
+    ///
+    /// ```ignore
+    /// ParamSwitch::new(cx, Data::params, |p| &p.bypass).labels(["OFF", "ON"]);
+    /// ```
+    fn labels<S: Into<String>>(self, labels: impl IntoIterator<Item = S>) -> Self;
+
+    /// Only move the switch while the mouse is held down on it, and go back to the parameter's
+    /// default value once it's released.
+    fn momentary(self, momentary: bool) -> Self;
+}
+
+impl ParamSwitchModifiers for Handle<'_, ParamSwitch> {
+    fn labels<S: Into<String>>(self, labels: impl IntoIterator<Item = S>) -> Self {
+        let labels = labels.into_iter().map(Into::into).collect();
+        self.modify(|switch| switch.labels = labels)
+    }
+
+    fn momentary(self, momentary: bool) -> Self {
+        self.modify(|switch| switch.momentary = momentary)
     }
 }
 
@@ -49,14 +174,87 @@ impl View for ParamSwitch {
         Some("paramswitch")
     }
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        event.map(|param_slider_event, meta| match param_slider_event {
-            ParamSwitchEvent::Switch => {
-                let value = if self.param_base.unmodulated_normalized_value() > 0.5 { 0.0 } else { 1.0 };
-                self.param_base.begin_set_parameter(cx);
-                self.param_base.set_normalized_value(cx, value);
-                self.param_base.end_set_parameter(cx);
+        if self.context_menu.handle_event(cx, event) {
+            return;
+        }
+
+        event.map(|window_event: &WindowEvent, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                cx.focus();
+
+                if cx.modifiers().command() {
+                    self.set(cx, self.param_base.default_normalized_value());
+                } else if self.momentary {
+                    // The gesture lasts until the mouse is released
+                    self.pressed = true;
+                    cx.capture();
+                    cx.set_active(true);
+
+                    self.param_base.begin_set_parameter(cx);
+                    self.param_base.set_normalized_value(cx, self.step(1, true));
+                } else if cx.modifiers().shift() {
+                    self.set(cx, self.step(-1, true));
+                } else {
+                    self.set(cx, self.step(1, true));
+                }
+
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.pressed {
+                    self.pressed = false;
+                    cx.release();
+                    cx.set_active(false);
+
+                    self.param_base
+                        .set_normalized_value(cx, self.param_base.default_normalized_value());
+                    self.param_base.end_set_parameter(cx);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseScroll(_scroll_x, scroll_y) => {
+                if self.pressed {
+                    return;
+                }
+
+                // With a regular scroll wheel `scroll_y` will only ever be -1 or 1, but with smooth
+                // scrolling trackpads being a thing `scroll_y` could be anything.
+                self.scrolled_lines += scroll_y;
+
+                if self.scrolled_lines.abs() >= 1.0 {
+                    let lines = self.scrolled_lines.trunc();
+                    self.scrolled_lines -= lines;
+                    self.set(cx, self.step(lines as isize, false));
+                }
+
+                meta.consume();
+            }
+            WindowEvent::KeyDown(Code::ArrowRight, _) | WindowEvent::KeyDown(Code::ArrowUp, _) => {
+                if self.pressed {
+                    return;
+                }
+
+                self.set(cx, self.step(1, false));
+                meta.consume();
+            }
+            WindowEvent::KeyDown(Code::ArrowLeft, _) | WindowEvent::KeyDown(Code::ArrowDown, _) => {
+                if self.pressed {
+                    return;
+                }
+
+                self.set(cx, self.step(-1, false));
+                meta.consume();
+            }
+            WindowEvent::KeyDown(Code::Space, _) | WindowEvent::KeyDown(Code::Enter, _) => {
+                if self.pressed || self.momentary {
+                    return;
+                }
+
+                self.set(cx, self.step(1, true));
                 meta.consume();
             }
+            _ => {}
         });
     }
 }
@@ -0,0 +1,47 @@
+//! How ASTRA's param widgets show a parameter's value. The selection and the displayed value always
+//! follow the unmodulated value the user set. Modulation is shown on top of that: sliders and knobs
+//! draw a `modulation` fill and head, and the steps of selectors and dropdowns, as well as
+//! switches, get the `modulated` class when modulation moves the parameter onto them.
+//!
+//! Stepped values are never compared with `==`. The normalized values the host sends and the ones
+//! computed from a step don't always round to the same float.
+
+use nih_plug::prelude::Param;
+
+/// How far apart two normalized values of a continuous parameter can be to still be the same.
+const CONTINUOUS_TOLERANCE: f32 = 1e-5;
+
+/// Whether two normalized values are on the same step of a parameter. Values of stepped parameters
+/// are allowed to be up to half a step apart.
+pub fn same_step<P: Param>(param: &P, a: f32, b: f32) -> bool {
+    let tolerance = match param.step_count() {
+        Some(step_count) if step_count > 0 => 0.5 / step_count as f32,
+        _ => CONTINUOUS_TOLERANCE,
+    };
+
+    (a - b).abs() < tolerance
+}
+
+/// Whether the parameter's unmodulated value is on the step at `normalized_value`.
+pub fn is_selected<P: Param>(param: &P, normalized_value: f32) -> bool {
+    same_step(
+        param,
+        param.unmodulated_normalized_value(),
+        normalized_value,
+    )
+}
+
+/// Whether modulation moves the parameter onto the step at `normalized_value`, away from the step
+/// that's selected.
+pub fn is_modulated_to<P: Param>(param: &P, normalized_value: f32) -> bool {
+    is_modulated(param) && same_step(param, param.modulated_normalized_value(), normalized_value)
+}
+
+/// Whether modulation moves the parameter to a different step than its unmodulated value.
+pub fn is_modulated<P: Param>(param: &P) -> bool {
+    !same_step(
+        param,
+        param.unmodulated_normalized_value(),
+        param.modulated_normalized_value(),
+    )
+}
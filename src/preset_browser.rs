@@ -0,0 +1,589 @@
+use std::sync::Arc;
+
+use nih_plug::prelude::{GuiContext, ParamPtr, Params};
+use nih_plug_vizia::{
+    vizia::{
+        icons::{ICON_CHEVRON_DOWN, ICON_CHEVRON_LEFT, ICON_CHEVRON_RIGHT},
+        prelude::*,
+    },
+    widgets::RawParamEvent,
+};
+
+use crate::{
+    presets::{self, Preset, PresetStore, USER_BANK},
+    tag::Tag,
+};
+
+/// Keeps track of the presets in a [`PresetStore`] and of which one is loaded, and loads and saves
+/// them through the editor's [`GuiContext`]. This needs to be built once, before any
+/// [`PresetBrowser`]s, and everything else is done by emitting [`PresetEvent`]s.
+///
+/// ```ignore
+/// create_vizia_editor(editor_state, ViziaTheming::None, move |cx, gui| {
+///     let directory = PresetStore::user_directory("Voidstar Audio", "My Plugin").unwrap();
+///     PresetManager::new(gui, params.as_ref(), PresetStore::new(directory)).build(cx);
+///
+///     PresetBrowser::new(cx);
+/// });
+/// ```
+#[derive(Lens)]
+pub struct PresetManager {
+    /// Every preset in the store, sorted by bank, category and name.
+    pub presets: Vec<Preset>,
+    /// The last preset that was loaded or saved.
+    pub current: Option<Preset>,
+    /// Whether the parameters have changed since the current preset was loaded or saved.
+    pub modified: bool,
+    /// What the [`PresetBrowser`]'s list is filtered by.
+    pub query: String,
+    pub dialog: PresetDialog,
+    /// The values being entered in the open dialog.
+    pub draft_name: String,
+    pub draft_category: String,
+    /// Comma separated.
+    pub draft_tags: String,
+    /// The last thing that went wrong, until the next action succeeds.
+    pub error: Option<String>,
+
+    #[lens(ignore)]
+    store: PresetStore,
+    #[lens(ignore)]
+    gui: Arc<dyn GuiContext>,
+    #[lens(ignore)]
+    params: Vec<ParamPtr>,
+    /// Every parameter's normalized value when the current preset was loaded or saved, in the
+    /// same order as `params`, to compare the parameters against.
+    #[lens(ignore)]
+    loaded: Option<Vec<f32>>,
+}
+
+/// The dialogs the [`PresetBrowser`] can show below its list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
+pub enum PresetDialog {
+    #[default]
+    None,
+    SaveAs,
+    Rename,
+    Delete,
+}
+
+pub enum PresetEvent {
+    Load(Preset),
+    /// Load the next preset in the list, or the first one if none is loaded.
+    Next,
+    /// Load the previous preset in the list, or the last one if none is loaded.
+    Previous,
+    /// Overwrite the current preset with the parameters' values, or open the save-as dialog if
+    /// there is no current preset.
+    Save,
+    /// Save the parameters' values as a new preset in the [`USER_BANK`].
+    SaveAs {
+        name: String,
+        category: String,
+        tags: Vec<String>,
+    },
+    /// Rename the current preset.
+    Rename(String),
+    /// Delete the current preset.
+    Delete,
+    /// Read the list of presets from disk again.
+    Rescan,
+
+    SetQuery(String),
+    OpenDialog(PresetDialog),
+    CloseDialog,
+    SetDraftName(String),
+    SetDraftCategory(String),
+    SetDraftTags(String),
+    /// Carry out the open dialog with the drafted values.
+    ConfirmDialog,
+}
+
+impl PresetManager {
+    pub fn new(gui: Arc<dyn GuiContext>, params: &dyn Params, store: PresetStore) -> Self {
+        let mut manager = Self {
+            presets: Vec::new(),
+            current: None,
+            modified: false,
+            query: String::new(),
+            dialog: PresetDialog::None,
+            draft_name: String::new(),
+            draft_category: String::new(),
+            draft_tags: String::new(),
+            error: None,
+            store,
+            gui,
+            params: params
+                .param_map()
+                .into_iter()
+                .map(|(_, param_ptr, _)| param_ptr)
+                .collect(),
+            loaded: None,
+        };
+        manager.rescan();
+
+        manager
+    }
+
+    fn rescan(&mut self) {
+        match self.store.presets() {
+            Ok(presets) => self.presets = presets,
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Every parameter's normalized value.
+    fn values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.params
+            .iter()
+            .map(|param| unsafe { param.unmodulated_normalized_value() })
+    }
+
+    /// Remember `preset` as the current one, with the parameters as they are now.
+    fn set_current(&mut self, preset: Option<Preset>) {
+        self.loaded = preset.as_ref().map(|_| self.values().collect());
+        self.current = preset;
+        self.modified = false;
+    }
+
+    fn load(&mut self, preset: &Preset) {
+        match self.store.load(preset, self.gui.get_state()) {
+            Ok(state) => {
+                self.gui.set_state(state);
+                self.set_current(Some(preset.clone()));
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Load the preset `offset` places away from the current one, wrapping around at the ends.
+    fn step(&mut self, offset: isize) {
+        let len = self.presets.len() as isize;
+        if len == 0 {
+            return;
+        }
+
+        let current = self
+            .current
+            .as_ref()
+            .and_then(|current| self.presets.iter().position(|preset| preset == current));
+        let index = match current {
+            Some(index) => (index as isize + offset).rem_euclid(len),
+            None if offset > 0 => 0,
+            None => len - 1,
+        };
+
+        let preset = self.presets[index as usize].clone();
+        self.load(&preset);
+    }
+
+    /// Carry out a change to the presets on disk, and rescan them if it worked.
+    fn apply(&mut self, result: Result<Option<Preset>, presets::PresetError>) -> bool {
+        match result {
+            Ok(preset) => {
+                self.set_current(preset);
+                self.rescan();
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                false
+            }
+        }
+    }
+
+    fn open_dialog(&mut self, dialog: PresetDialog) {
+        if dialog != PresetDialog::SaveAs && self.current.is_none() {
+            return;
+        }
+
+        let current = self.current.as_ref();
+        self.draft_name = current
+            .map(|preset| preset.name.clone())
+            .unwrap_or_default();
+        self.draft_category = current
+            .map(|preset| preset.category.clone())
+            .unwrap_or_default();
+        self.draft_tags = current
+            .map(|preset| preset.tags.join(", "))
+            .unwrap_or_default();
+        self.dialog = dialog;
+        self.error = None;
+    }
+
+    fn confirm_dialog(&mut self) {
+        let done = match self.dialog {
+            PresetDialog::None => true,
+            PresetDialog::SaveAs => {
+                let (name, category) = (self.draft_name.clone(), self.draft_category.clone());
+                let tags = self
+                    .draft_tags
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from)
+                    .collect();
+                self.save_as(&name, &category, tags)
+            }
+            PresetDialog::Rename => {
+                let name = self.draft_name.clone();
+                self.rename(&name)
+            }
+            PresetDialog::Delete => self.delete(),
+        };
+
+        if done {
+            self.dialog = PresetDialog::None;
+        }
+    }
+
+    fn save_as(&mut self, name: &str, category: &str, tags: Vec<String>) -> bool {
+        let state = self.gui.get_state();
+        let result = self
+            .store
+            .save(USER_BANK, name, category.trim(), &tags, &state);
+
+        self.apply(result.map(Some))
+    }
+
+    fn rename(&mut self, name: &str) -> bool {
+        let Some(current) = self.current.clone() else {
+            return false;
+        };
+
+        // Renaming doesn't touch the preset's values, so it stays modified if it was
+        match self.store.rename(&current, name) {
+            Ok(preset) => {
+                self.current = Some(preset);
+                self.rescan();
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                false
+            }
+        }
+    }
+
+    fn delete(&mut self) -> bool {
+        let Some(current) = self.current.clone() else {
+            return false;
+        };
+
+        let result = self.store.delete(&current);
+        self.apply(result.map(|_| None))
+    }
+
+    /// Compare the parameters with the current preset. This runs after every gesture and every
+    /// change from the host, so it reads the normalized values directly instead of serializing the
+    /// plugin's state.
+    fn update_modified(&mut self) {
+        self.modified = match &self.loaded {
+            Some(loaded) => !self.values().eq(loaded.iter().copied()),
+            None => false,
+        };
+    }
+}
+
+impl Model for PresetManager {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|preset_event, meta| {
+            match preset_event {
+                PresetEvent::Load(preset) => self.load(preset),
+                PresetEvent::Next => self.step(1),
+                PresetEvent::Previous => self.step(-1),
+                PresetEvent::Save => match self.current.clone() {
+                    Some(current) => {
+                        let result = self.store.overwrite(&current, &self.gui.get_state());
+                        self.apply(result.map(Some));
+                    }
+                    None => self.open_dialog(PresetDialog::SaveAs),
+                },
+                PresetEvent::SaveAs {
+                    name,
+                    category,
+                    tags,
+                } => {
+                    self.save_as(name, category, tags.clone());
+                }
+                PresetEvent::Rename(name) => {
+                    self.rename(name);
+                }
+                PresetEvent::Delete => {
+                    self.delete();
+                }
+                PresetEvent::Rescan => self.rescan(),
+                PresetEvent::SetQuery(query) => self.query = query.clone(),
+                PresetEvent::OpenDialog(dialog) => self.open_dialog(*dialog),
+                PresetEvent::CloseDialog => {
+                    self.dialog = PresetDialog::None;
+                    self.error = None;
+                }
+                PresetEvent::SetDraftName(name) => self.draft_name = name.clone(),
+                PresetEvent::SetDraftCategory(category) => self.draft_category = category.clone(),
+                PresetEvent::SetDraftTags(tags) => self.draft_tags = tags.clone(),
+                PresetEvent::ConfirmDialog => self.confirm_dialog(),
+            }
+
+            meta.consume();
+        });
+
+        // Parameter changes from the host arrive as `ParametersChanged`, and ones from the editor
+        // are done by the time a gesture ends
+        event.map(|param_event, _| match param_event {
+            RawParamEvent::ParametersChanged | RawParamEvent::EndSetParameter(_) => {
+                self.update_modified()
+            }
+            _ => {}
+        });
+    }
+}
+
+/// A preset selector with a dropdown of every preset in the [`PresetManager`], buttons for
+/// stepping through them, and a save button. The dropdown's list can be searched by name, bank,
+/// category and tag, and it has dialogs for saving, renaming and deleting presets. While the
+/// parameters differ from the current preset, the view has the `modified` class.
+pub struct PresetBrowser;
+
+impl PresetBrowser {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::Previous),
+                |cx| Label::new(cx, ICON_CHEVRON_LEFT),
+            )
+            .class("previous");
+
+            Dropdown::new(
+                cx,
+                |cx| {
+                    HStack::new(cx, |cx| {
+                        Label::new(
+                            cx,
+                            PresetManager::current.map(|current| match current {
+                                Some(preset) => preset.name.to_uppercase(),
+                                None => String::from("NO PRESET"),
+                            }),
+                        )
+                        .class("name")
+                        .pointer_events(false);
+                        Label::new(cx, "*")
+                            .class("modified-indicator")
+                            .display(PresetManager::modified)
+                            .pointer_events(false);
+                        Label::new(cx, ICON_CHEVRON_DOWN).pointer_events(false);
+                    })
+                },
+                |cx| {
+                    Textbox::new(cx, PresetManager::query)
+                        .on_edit(|cx, query| cx.emit(PresetEvent::SetQuery(query)))
+                        .class("search");
+
+                    ScrollView::new(cx, 0.0, 0.0, false, true, |cx| {
+                        Binding::new(cx, PresetManager::query, |cx, query| {
+                            let query = query.get(cx);
+                            Binding::new(cx, PresetManager::presets, move |cx, presets| {
+                                preset_list(cx, &presets.get(cx), &query);
+                            });
+                        });
+                    })
+                    .class("list");
+
+                    Binding::new(cx, PresetManager::dialog, |cx, dialog| {
+                        dialog_view(cx, dialog.get(cx));
+                    });
+
+                    Label::new(
+                        cx,
+                        PresetManager::error.map(|error| error.clone().unwrap_or_default()),
+                    )
+                    .class("error")
+                    .display(PresetManager::error.map(|error| error.is_some()));
+                },
+            )
+            .class("presets");
+
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::Next),
+                |cx| Label::new(cx, ICON_CHEVRON_RIGHT),
+            )
+            .class("next");
+
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::Save),
+                |cx| Label::new(cx, "SAVE"),
+            )
+            .class("save");
+        })
+        .toggle_class("modified", PresetManager::modified)
+    }
+}
+
+impl View for PresetBrowser {
+    fn element(&self) -> Option<&'static str> {
+        Some("presetbrowser")
+    }
+}
+
+/// The presets that match `query`, under a title for every bank and category.
+fn preset_list(cx: &mut Context, presets: &[Preset], query: &str) {
+    let mut last_group = None;
+    let mut empty = true;
+
+    for preset in presets.iter().filter(|preset| preset.matches(query)) {
+        empty = false;
+
+        let group = (preset.bank.as_str(), preset.category.as_str());
+        if last_group != Some(group) {
+            let title = if preset.category.is_empty() {
+                preset.bank.to_uppercase()
+            } else {
+                format!("{} / {}", preset.bank, preset.category).to_uppercase()
+            };
+            Label::new(cx, title.as_str()).class("group-title");
+            last_group = Some(group);
+        }
+
+        let path = preset.path.clone();
+        let pressed = preset.clone();
+        HStack::new(cx, |cx| {
+            Label::new(cx, preset.name.as_str()).class("name");
+            for tag in &preset.tags {
+                Tag::new(cx, |cx| {
+                    Label::new(cx, tag.as_str());
+                });
+            }
+        })
+        .on_press(move |cx| {
+            cx.emit(PresetEvent::Load(pressed.clone()));
+            cx.emit(PopupEvent::Close);
+        })
+        .class("option")
+        .toggle_class(
+            "selected",
+            PresetManager::current
+                .map(move |current| current.as_ref().is_some_and(|c| c.path == path)),
+        );
+    }
+
+    if empty {
+        Label::new(cx, "NO PRESETS").class("empty");
+    }
+}
+
+/// The open dialog, or the buttons for opening them.
+fn dialog_view(cx: &mut Context, dialog: PresetDialog) {
+    if dialog == PresetDialog::None {
+        HStack::new(cx, |cx| {
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::OpenDialog(PresetDialog::SaveAs)),
+                |cx| Label::new(cx, "SAVE AS"),
+            );
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::OpenDialog(PresetDialog::Rename)),
+                |cx| Label::new(cx, "RENAME"),
+            );
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::OpenDialog(PresetDialog::Delete)),
+                |cx| Label::new(cx, "DELETE"),
+            )
+            .class("destructive");
+        })
+        .class("actions");
+
+        return;
+    }
+
+    VStack::new(cx, |cx| {
+        match dialog {
+            PresetDialog::SaveAs => {
+                dialog_field(
+                    cx,
+                    "NAME",
+                    PresetManager::draft_name,
+                    PresetEvent::SetDraftName,
+                );
+                dialog_field(
+                    cx,
+                    "CATEGORY",
+                    PresetManager::draft_category,
+                    PresetEvent::SetDraftCategory,
+                );
+                dialog_field(
+                    cx,
+                    "TAGS",
+                    PresetManager::draft_tags,
+                    PresetEvent::SetDraftTags,
+                );
+            }
+            PresetDialog::Rename => {
+                dialog_field(
+                    cx,
+                    "NAME",
+                    PresetManager::draft_name,
+                    PresetEvent::SetDraftName,
+                );
+            }
+            PresetDialog::Delete | PresetDialog::None => {
+                Label::new(
+                    cx,
+                    PresetManager::current.map(|current| {
+                        let name = current.as_ref().map(|preset| preset.name.as_str());
+                        format!("DELETE '{}'?", name.unwrap_or_default())
+                    }),
+                )
+                .class("question");
+            }
+        }
+
+        let (confirm, class) = match dialog {
+            PresetDialog::SaveAs => ("SAVE", "primary"),
+            PresetDialog::Rename => ("RENAME", "primary"),
+            PresetDialog::Delete | PresetDialog::None => ("DELETE", "destructive"),
+        };
+        HStack::new(cx, |cx| {
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::CloseDialog),
+                |cx| Label::new(cx, "CANCEL"),
+            );
+            Button::new(
+                cx,
+                |cx| cx.emit(PresetEvent::ConfirmDialog),
+                move |cx| Label::new(cx, confirm),
+            )
+            .class(class);
+        })
+        .class("actions");
+    })
+    .class("dialog");
+}
+
+/// A labeled textbox for one of the dialog's values. Pressing enter confirms the dialog.
+fn dialog_field(
+    cx: &mut Context,
+    name: &str,
+    lens: impl Lens<Target = String>,
+    on_edit: fn(String) -> PresetEvent,
+) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, name).class("name");
+        Textbox::new(cx, lens)
+            .on_edit(move |cx, text| cx.emit(on_edit(text)))
+            .on_submit(|cx, _, success| {
+                if success {
+                    cx.emit(PresetEvent::ConfirmDialog);
+                }
+            });
+    })
+    .class("field");
+}
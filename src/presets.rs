@@ -0,0 +1,340 @@
+//! Storing a plugin's parameter values as preset files. Presets are JSON files in a preset
+//! directory, where every subdirectory is a bank. Within a bank, presets can be sorted into
+//! categories and tagged, which is stored in the files themselves.
+//!
+//! ```text
+//! presets/
+//! ├── Factory/
+//! │   ├── Init.json
+//! │   └── Warm Pad.json
+//! └── User/
+//!     └── My Bass.json
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use nih_plug::wrapper::state::{ParamValue, PluginState};
+use nih_plug_vizia::vizia::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The bank new presets are saved to.
+pub const USER_BANK: &str = "User";
+
+const EXTENSION: &str = "json";
+
+/// A preset file that was found in a [`PresetStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    /// The file name without its extension.
+    pub name: String,
+    /// The directory the preset is in.
+    pub bank: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub path: PathBuf,
+}
+
+impl Preset {
+    /// Whether the preset's name, bank, category, or one of its tags contains `query`, ignoring
+    /// case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || [&self.name, &self.bank, &self.category]
+                .into_iter()
+                .chain(&self.tags)
+                .any(|text| text.to_lowercase().contains(&query))
+    }
+}
+
+impl Data for Preset {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// What's stored in a preset file. The name and the bank come from the file's path.
+#[derive(Debug, Serialize, Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// The plugin's version when the preset was saved.
+    version: String,
+    params: BTreeMap<String, ParamValue>,
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    /// A preset file or directory couldn't be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// A preset file isn't valid JSON, or doesn't contain a plugin's state.
+    Format {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// There's already a preset with this name in the bank.
+    AlreadyExists(String),
+    /// The name is empty, or can't be used as a file name.
+    InvalidName(String),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io { path, source } => {
+                write!(f, "could not access '{}': {source}", path.display())
+            }
+            PresetError::Format { path, source } => {
+                write!(f, "'{}' is not a valid preset: {source}", path.display())
+            }
+            PresetError::AlreadyExists(name) => write!(f, "a preset named '{name}' already exists"),
+            PresetError::InvalidName(name) => write!(f, "'{name}' is not a valid preset name"),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PresetError::Io { source, .. } => Some(source),
+            PresetError::Format { source, .. } => Some(source),
+            PresetError::AlreadyExists(_) | PresetError::InvalidName(_) => None,
+        }
+    }
+}
+
+/// Reads and writes the presets in a directory. Only the parameters' values are stored, the
+/// plugin's persistent fields are left alone when a preset is loaded.
+#[derive(Debug, Clone)]
+pub struct PresetStore {
+    directory: PathBuf,
+}
+
+impl PresetStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// The platform's usual place for a user's presets, like
+    /// `~/.local/share/<vendor>/<plugin>/Presets` on Linux or
+    /// `%APPDATA%\<vendor>\<plugin>\Presets` on Windows.
+    pub fn user_directory(vendor: &str, plugin: &str) -> Option<PathBuf> {
+        let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty());
+        let home = || env_dir("HOME").map(PathBuf::from);
+
+        let data_dir = if cfg!(target_os = "windows") {
+            env_dir("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            home().map(|home| home.join("Library/Application Support"))
+        } else {
+            env_dir("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| home().map(|home| home.join(".local/share")))
+        }?;
+
+        Some(data_dir.join(vendor).join(plugin).join("Presets"))
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// All presets, sorted by bank, category and name. A missing preset directory counts as an
+    /// empty one. Files that aren't valid presets are skipped.
+    pub fn presets(&self) -> Result<Vec<Preset>, PresetError> {
+        let mut presets = Vec::new();
+
+        for bank in read_dir(&self.directory)? {
+            if !bank.is_dir() {
+                continue;
+            }
+            let bank_name = file_stem(&bank);
+
+            for path in read_dir(&bank)? {
+                if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION) {
+                    continue;
+                }
+                if let Ok(file) = read_file(&path) {
+                    presets.push(Preset {
+                        name: file_stem(&path),
+                        bank: bank_name.clone(),
+                        category: file.category,
+                        tags: file.tags,
+                        path,
+                    });
+                }
+            }
+        }
+
+        presets.sort_by(|a, b| {
+            (&a.bank, &a.category, a.name.to_lowercase()).cmp(&(
+                &b.bank,
+                &b.category,
+                b.name.to_lowercase(),
+            ))
+        });
+        Ok(presets)
+    }
+
+    /// Read a preset and apply its parameter values to `state`, which is usually the plugin's
+    /// current state. Parameters the preset doesn't know about keep their current values.
+    pub fn load(
+        &self,
+        preset: &Preset,
+        mut state: PluginState,
+    ) -> Result<PluginState, PresetError> {
+        let file = read_file(&preset.path)?;
+        state.params.extend(file.params);
+
+        Ok(state)
+    }
+
+    /// Save the parameter values in `state` as a new preset in `bank`.
+    pub fn save(
+        &self,
+        bank: &str,
+        name: &str,
+        category: &str,
+        tags: &[String],
+        state: &PluginState,
+    ) -> Result<Preset, PresetError> {
+        let path = self.preset_path(bank, name)?;
+        if path.exists() {
+            return Err(PresetError::AlreadyExists(name.to_owned()));
+        }
+
+        self.write(bank, name, category, tags, state, path)
+    }
+
+    /// Replace a preset's parameter values with the ones in `state`.
+    pub fn overwrite(&self, preset: &Preset, state: &PluginState) -> Result<Preset, PresetError> {
+        self.write(
+            &preset.bank,
+            &preset.name,
+            &preset.category,
+            &preset.tags,
+            state,
+            preset.path.clone(),
+        )
+    }
+
+    pub fn rename(&self, preset: &Preset, name: &str) -> Result<Preset, PresetError> {
+        let path = self.preset_path(&preset.bank, name)?;
+        if path.exists() && path != preset.path {
+            return Err(PresetError::AlreadyExists(name.to_owned()));
+        }
+
+        fs::rename(&preset.path, &path).map_err(|source| PresetError::Io {
+            path: preset.path.clone(),
+            source,
+        })?;
+
+        Ok(Preset {
+            name: name.trim().to_owned(),
+            path,
+            ..preset.clone()
+        })
+    }
+
+    pub fn delete(&self, preset: &Preset) -> Result<(), PresetError> {
+        fs::remove_file(&preset.path).map_err(|source| PresetError::Io {
+            path: preset.path.clone(),
+            source,
+        })
+    }
+
+    fn write(
+        &self,
+        bank: &str,
+        name: &str,
+        category: &str,
+        tags: &[String],
+        state: &PluginState,
+        path: PathBuf,
+    ) -> Result<Preset, PresetError> {
+        let file = PresetFile {
+            category: category.to_owned(),
+            tags: tags.to_vec(),
+            version: state.version.clone(),
+            params: state.params.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(|source| PresetError::Format {
+            path: path.clone(),
+            source,
+        })?;
+
+        let io_error = |source| PresetError::Io {
+            path: path.clone(),
+            source,
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(io_error)?;
+        }
+        fs::write(&path, json).map_err(io_error)?;
+
+        Ok(Preset {
+            name: name.trim().to_owned(),
+            bank: bank.to_owned(),
+            category: file.category,
+            tags: file.tags,
+            path,
+        })
+    }
+
+    fn preset_path(&self, bank: &str, name: &str) -> Result<PathBuf, PresetError> {
+        let name = name.trim();
+        let invalid = |c: char| c.is_control() || "/\\:*?\"<>|".contains(c);
+        if name.is_empty() || name.starts_with('.') || name.contains(invalid) {
+            return Err(PresetError::InvalidName(name.to_owned()));
+        }
+
+        Ok(self
+            .directory
+            .join(bank)
+            .join(format!("{name}.{EXTENSION}")))
+    }
+}
+
+fn read_dir(directory: &Path) -> Result<Vec<PathBuf>, PresetError> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(PresetError::Io {
+                path: directory.to_owned(),
+                source,
+            })
+        }
+    };
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect())
+}
+
+fn read_file(path: &Path) -> Result<PresetFile, PresetError> {
+    let json = fs::read_to_string(path).map_err(|source| PresetError::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+
+    serde_json::from_str(&json).map_err(|source| PresetError::Format {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -1,29 +1,186 @@
+use std::rc::Rc;
+
 use nih_plug::params::enums::Enum;
 use nih_plug_vizia::vizia::prelude::*;
 
+use crate::orientation::Orientation;
+
 enum SelectorEvent {
     Update(usize),
 }
 
+/// One of the buttons of a [`Selector`] that's built from a list with [`Selector::with_items()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorItem {
+    pub label: String,
+    /// Whether the label is one of vizia's icons, like
+    /// [`ICON_CHEVRON_DOWN`][nih_plug_vizia::vizia::icons::ICON_CHEVRON_DOWN]. Icon labels get
+    /// the `icon` class.
+    pub icon: bool,
+    /// Disabled items can't be selected, and get the `disabled` class.
+    pub disabled: bool,
+}
+
+impl SelectorItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            icon: false,
+            disabled: false,
+        }
+    }
+
+    pub fn icon(icon: impl Into<String>) -> Self {
+        Self {
+            icon: true,
+            ..Self::new(icon)
+        }
+    }
+
+    pub fn disabled(self, disabled: bool) -> Self {
+        Self { disabled, ..self }
+    }
+}
+
+impl From<&str> for SelectorItem {
+    fn from(label: &str) -> Self {
+        Self::new(label)
+    }
+}
+
+impl From<String> for SelectorItem {
+    fn from(label: String) -> Self {
+        Self::new(label)
+    }
+}
+
+/// A row of buttons for picking one of an enum's variants, or one of the items in a list. The
+/// selector is focused as a whole, and its selection can be moved with the arrow keys (left and
+/// right, or up and down when it's vertical) and Home and End.
 #[derive(Lens)]
 pub struct Selector {
     on_toggle_action: Option<Box<dyn Fn(&mut EventContext, usize)>>,
+    orientation: Orientation,
+    /// The index of the selected button, which the keyboard navigation moves from.
+    selected: usize,
+    /// Whether each button is disabled.
+    disabled: Vec<bool>,
 }
 
 impl Selector {
     pub fn new<E: Enum + Clone>(cx: &mut Context, data: impl Lens<Target = E>) -> Handle<Self> {
         Self {
             on_toggle_action: None,
+            orientation: Orientation::default(),
+            selected: 0,
+            disabled: vec![false; E::variants().len()],
         }
         .build(cx, |cx| {
             HStack::new(cx, |cx| {
                 for (i, variant) in E::variants().iter().enumerate() {
                     Button::new(cx, |_| {}, |cx| Label::new(cx, *variant))
                         .on_press(move |cx| cx.emit(SelectorEvent::Update(i)))
-                        .toggle_class("on", data.map(move |d| d.clone().to_index() == i));
+                        .toggle_class("on", data.map(move |d| d.clone().to_index() == i))
+                        .navigable(false);
                 }
-            });
+            })
+            .layout_type(Selector::orientation.map(|orientation| layout_type(*orientation)));
+        })
+        .bind(data, |mut handle, data| {
+            let selected = data.get(&*handle.context()).to_index();
+            handle.modify(|selector| selector.selected = selected);
         })
+        .toggle_class(
+            "vertical",
+            Selector::orientation.map(|orientation| *orientation == Orientation::Vertical),
+        )
+        .navigable(true)
+    }
+
+    /// A selector with a button for every item in a list, which is rebuilt when the list changes.
+    /// `item` turns an item into its button's label, and `selected` is the index of the selected
+    /// item. [`SelectorModifiers::on_toggle()`] gets the index of the item that was picked.
+    ///
+    /// ```ignore
+    /// Selector::with_items(cx, Data::oversampling_factors, Data::oversampling, |factor| {
+    ///     SelectorItem::new(format!("{factor}X")).disabled(*factor > 8)
+    /// })
+    /// .on_toggle(|cx, i| cx.emit(AppEvent::SetOversampling(i)));
+    /// ```
+    pub fn with_items<L, T, F>(
+        cx: &mut Context,
+        items: L,
+        selected: impl Lens<Target = usize>,
+        item: F,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Vec<T>>,
+        T: Data,
+        F: 'static + Fn(&T) -> SelectorItem,
+    {
+        let item = Rc::new(item);
+        let disabled_item = item.clone();
+
+        Self {
+            on_toggle_action: None,
+            orientation: Orientation::default(),
+            selected: 0,
+            disabled: Vec::new(),
+        }
+        .build(cx, move |cx| {
+            HStack::new(cx, move |cx| {
+                Binding::new(cx, items, move |cx, items| {
+                    for (i, value) in items.get(cx).iter().enumerate() {
+                        let SelectorItem {
+                            label,
+                            icon,
+                            disabled,
+                        } = item(value);
+
+                        Button::new(
+                            cx,
+                            |_| {},
+                            move |cx| Label::new(cx, label.as_str()).toggle_class("icon", icon),
+                        )
+                        .on_press(move |cx| cx.emit(SelectorEvent::Update(i)))
+                        .toggle_class("on", selected.map(move |selected| *selected == i))
+                        .toggle_class("disabled", disabled)
+                        .navigable(false);
+                    }
+                });
+            })
+            .layout_type(Selector::orientation.map(|orientation| layout_type(*orientation)));
+        })
+        .bind(items, move |mut handle, items| {
+            let disabled = items
+                .get(&*handle.context())
+                .iter()
+                .map(|i| disabled_item(i).disabled)
+                .collect();
+            handle.modify(|selector| selector.disabled = disabled);
+        })
+        .bind(selected, |mut handle, selected| {
+            let selected = selected.get(&*handle.context());
+            handle.modify(|selector| selector.selected = selected);
+        })
+        .toggle_class(
+            "vertical",
+            Selector::orientation.map(|orientation| *orientation == Orientation::Vertical),
+        )
+        .navigable(true)
+    }
+
+    /// The enabled button a key moves the selection to, if any.
+    fn navigate(&self, key: SelectorKey) -> Option<usize> {
+        let enabled = |i: &usize| !self.disabled[*i];
+        let count = self.disabled.len();
+
+        match key {
+            SelectorKey::Previous => (0..self.selected.min(count)).rev().find(enabled),
+            SelectorKey::Next => (self.selected + 1..count).find(enabled),
+            SelectorKey::First => (0..count).find(enabled),
+            SelectorKey::Last => (0..count).rev().find(enabled),
+        }
     }
 }
 
@@ -31,6 +188,9 @@ pub trait SelectorModifiers {
     fn on_toggle<F>(self, callback: F) -> Self
     where
         F: 'static + Fn(&mut EventContext, usize);
+
+    /// Lay the buttons out in a row (the default) or in a column.
+    fn orientation(self, orientation: Orientation) -> Self;
 }
 
 impl SelectorModifiers for Handle<'_, Selector> {
@@ -40,6 +200,10 @@ impl SelectorModifiers for Handle<'_, Selector> {
     {
         self.modify(|selector| selector.on_toggle_action = Some(Box::new(callback)))
     }
+
+    fn orientation(self, orientation: Orientation) -> Self {
+        self.modify(|selector| selector.orientation = orientation)
+    }
 }
 
 impl View for Selector {
@@ -49,10 +213,59 @@ impl View for Selector {
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|selector_event, _| match selector_event {
             SelectorEvent::Update(i) => {
+                cx.focus();
+                if self.disabled.get(*i) == Some(&true) {
+                    return;
+                }
                 if let Some(ref f) = self.on_toggle_action {
                     f(cx, *i);
                 }
             }
         });
+
+        event.map(|window_event: &WindowEvent, meta| {
+            if let WindowEvent::KeyDown(code, _) = window_event {
+                let Some(key) = SelectorKey::from_code(*code, self.orientation) else {
+                    return;
+                };
+                if let Some(i) = self.navigate(key).filter(|i| *i != self.selected) {
+                    if let Some(ref f) = self.on_toggle_action {
+                        f(cx, i);
+                    }
+                }
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// How a key moves the selection of a [`Selector`] or a
+/// [`ParamSelector`][crate::param_selector::ParamSelector].
+pub(crate) enum SelectorKey {
+    Previous,
+    Next,
+    First,
+    Last,
+}
+
+impl SelectorKey {
+    pub(crate) fn from_code(code: Code, orientation: Orientation) -> Option<Self> {
+        match (code, orientation) {
+            (Code::ArrowLeft, Orientation::Horizontal) | (Code::ArrowUp, Orientation::Vertical) => {
+                Some(SelectorKey::Previous)
+            }
+            (Code::ArrowRight, Orientation::Horizontal)
+            | (Code::ArrowDown, Orientation::Vertical) => Some(SelectorKey::Next),
+            (Code::Home, _) => Some(SelectorKey::First),
+            (Code::End, _) => Some(SelectorKey::Last),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn layout_type(orientation: Orientation) -> LayoutType {
+    match orientation {
+        Orientation::Horizontal => LayoutType::Row,
+        Orientation::Vertical => LayoutType::Column,
     }
 }
@@ -0,0 +1,657 @@
+use std::{
+    cell::RefCell,
+    f32::consts::PI,
+    ops::RangeInclusive,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use nih_plug::util;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+use crate::bridge::{redraw_continuously, RedrawEvent, RingBufferOutput};
+
+/// A spectrum analyzer for the samples the plugin pushes into a
+/// [`ring_buffer()`][crate::bridge::ring_buffer]. The FFT is computed on the GUI thread while
+/// drawing, so the audio thread only needs to push samples (usually a mono mix of all channels)
+/// into the ring buffer.
+///
+/// Frequencies are laid out logarithmically. Rising levels are shown immediately, while falling
+/// levels decay at a fixed rate, and a peak hold line can be drawn on top.
+#[derive(Lens)]
+pub struct SpectrumAnalyzer {
+    min_frequency: f32,
+    max_frequency: f32,
+    fft_size: usize,
+    decay: f32,
+    peak_hold: bool,
+}
+
+const DEFAULT_FFT_SIZE: usize = 2048;
+const DEFAULT_FREQUENCY_RANGE: RangeInclusive<f32> = 20.0..=20_000.0;
+/// In decibels per second.
+const DEFAULT_DECAY: f32 = 60.0;
+/// The rate the peak hold line falls at once it has been held for [`PEAK_HOLD_TIME`], in decibels
+/// per second.
+const PEAK_HOLD_DECAY: f32 = 6.0;
+const PEAK_HOLD_TIME: Duration = Duration::from_secs(2);
+
+impl SpectrumAnalyzer {
+    /// Create a spectrum analyzer reading samples from `samples`, which were recorded at
+    /// `sample_rate`, and displaying levels in the `db_range`.
+    pub fn new<S, R>(
+        cx: &mut Context,
+        samples: S,
+        sample_rate: R,
+        db_range: RangeInclusive<f32>,
+    ) -> Handle<Self>
+    where
+        S: Lens<Target = RingBufferOutput<f32>>,
+        R: Lens<Target = f32>,
+    {
+        let (min_db, max_db) = (*db_range.start(), *db_range.end());
+        let state = Rc::new(RefCell::new(AnalyzerState::default()));
+
+        Self {
+            min_frequency: *DEFAULT_FREQUENCY_RANGE.start(),
+            max_frequency: *DEFAULT_FREQUENCY_RANGE.end(),
+            fft_size: DEFAULT_FFT_SIZE,
+            decay: DEFAULT_DECAY,
+            peak_hold: false,
+        }
+        .build(cx, |cx| {
+            // The samples are analyzed while the spectrum is drawn, and the levels decay between
+            // blocks, so this redraws every frame
+            redraw_continuously(cx);
+
+            ZStack::new(cx, |cx| {
+                let layer = |kind| SpectrumLayer {
+                    kind,
+                    state: state.clone(),
+                    min_db,
+                    max_db,
+                };
+
+                AnalyzerInput {
+                    samples,
+                    sample_rate,
+                    state: state.clone(),
+                }
+                .build(cx, |_| {});
+
+                layer(SpectrumLayerKind::Grid)
+                    .build(cx, |_| {})
+                    .class("grid");
+                layer(SpectrumLayerKind::Spectrum)
+                    .build(cx, |_| {})
+                    .class("spectrum");
+                let peak_state = state.clone();
+                Binding::new(cx, SpectrumAnalyzer::peak_hold, move |cx, peak_hold| {
+                    if peak_hold.get(cx) {
+                        SpectrumLayer {
+                            kind: SpectrumLayerKind::PeakHold,
+                            state: peak_state.clone(),
+                            min_db,
+                            max_db,
+                        }
+                        .build(cx, |_| {})
+                        .class("peak-hold");
+                    }
+                });
+
+                Binding::new(
+                    cx,
+                    SpectrumAnalyzer::min_frequency,
+                    move |cx, min_frequency| {
+                        let min_frequency = min_frequency.get(cx);
+                        Binding::new(
+                            cx,
+                            SpectrumAnalyzer::max_frequency,
+                            move |cx, max_frequency| {
+                                grid_labels(
+                                    cx,
+                                    min_frequency,
+                                    max_frequency.get(cx),
+                                    min_db,
+                                    max_db,
+                                );
+                            },
+                        );
+                    },
+                );
+            })
+            .class("display")
+            .hoverable(false);
+        })
+    }
+}
+
+pub trait SpectrumAnalyzerModifiers {
+    /// The range of frequencies to display, in Hertz. Defaults to 20 Hz through 20 kHz.
+    fn frequency_range(self, range: RangeInclusive<f32>) -> Self;
+
+    /// The number of samples analyzed at once, rounded up to the next power of two. Larger sizes
+    /// resolve lower frequencies but react slower. Defaults to 2048.
+    fn fft_size(self, size: usize) -> Self;
+
+    /// How quickly the spectrum falls when the level drops, in decibels per second. Defaults to 60
+    /// dB/s.
+    fn decay(self, decay: f32) -> Self;
+
+    /// Draw a line at the highest recent level of every frequency.
+    fn peak_hold(self, peak_hold: bool) -> Self;
+}
+
+impl SpectrumAnalyzerModifiers for Handle<'_, SpectrumAnalyzer> {
+    fn frequency_range(self, range: RangeInclusive<f32>) -> Self {
+        self.modify(|analyzer| {
+            analyzer.min_frequency = range.start().max(f32::EPSILON);
+            analyzer.max_frequency = range.end().max(analyzer.min_frequency * 2.0);
+        })
+    }
+
+    fn fft_size(self, size: usize) -> Self {
+        self.modify(|analyzer| analyzer.fft_size = size.max(16).next_power_of_two())
+    }
+
+    fn decay(self, decay: f32) -> Self {
+        self.modify(|analyzer| analyzer.decay = decay.max(0.0))
+    }
+
+    fn peak_hold(self, peak_hold: bool) -> Self {
+        self.modify(|analyzer| analyzer.peak_hold = peak_hold)
+    }
+}
+
+impl View for SpectrumAnalyzer {
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrumanalyzer")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|RedrawEvent, meta| {
+            cx.needs_redraw();
+            meta.consume();
+        });
+    }
+}
+
+/// The position of `frequency` on a logarithmic axis from `min_frequency` to `max_frequency`.
+fn frequency_to_t(frequency: f32, min_frequency: f32, max_frequency: f32) -> f32 {
+    (frequency / min_frequency).ln() / (max_frequency / min_frequency).ln()
+}
+
+/// Grid lines at every multiple of a power of ten within the range. The ones at 1, 2 and 5 times a
+/// power of ten are labeled.
+fn frequency_grid(min_frequency: f32, max_frequency: f32) -> impl Iterator<Item = (f32, bool)> {
+    let first_decade = min_frequency.log10().floor() as i32;
+    let last_decade = max_frequency.log10().ceil() as i32;
+
+    (first_decade..=last_decade)
+        .flat_map(|decade| {
+            let power = 10f32.powi(decade);
+            (1..10).map(move |multiple| (multiple as f32 * power, matches!(multiple, 1 | 2 | 5)))
+        })
+        .filter(move |(frequency, _)| *frequency > min_frequency && *frequency < max_frequency)
+}
+
+/// A label for every labeled grid line, positioned the same way [`SpectrumLayerKind::Grid`]
+/// draws its lines.
+fn grid_labels(cx: &mut Context, min_frequency: f32, max_frequency: f32, min_db: f32, max_db: f32) {
+    for (frequency, labeled) in frequency_grid(min_frequency, max_frequency) {
+        if !labeled {
+            continue;
+        }
+
+        let label = if frequency >= 1000.0 {
+            format!("{}k", frequency / 1000.0)
+        } else {
+            format!("{frequency}")
+        };
+        let t = frequency_to_t(frequency, min_frequency, max_frequency);
+        Label::new(cx, label.as_str())
+            .class("grid-label")
+            .class("frequency")
+            .left(Units::Percentage(t * 100.0));
+    }
+
+    for db in db_grid(min_db, max_db) {
+        let t = (db - min_db) / (max_db - min_db);
+        Label::new(cx, format!("{db:.0}").as_str())
+            .class("grid-label")
+            .class("db")
+            .top(Stretch(1.0))
+            .bottom(Units::Percentage(t * 100.0));
+    }
+}
+
+/// Lines every 6, 12 or 24 decibels, skipping the edges of the range.
+fn db_grid(min_db: f32, max_db: f32) -> impl Iterator<Item = f32> {
+    let span = max_db - min_db;
+    let step = [6.0, 12.0, 24.0]
+        .into_iter()
+        .find(|step| span / step <= 8.0)
+        .unwrap_or(48.0);
+
+    let first = (min_db / step).floor() as i32 + 1;
+    let last = (max_db / step).ceil() as i32 - 1;
+    (first..=last).map(move |i| i as f32 * step)
+}
+
+/// The analysis state shared between the analyzer's layers. This is updated by the
+/// [`AnalyzerInput`], which is drawn first, once per frame.
+#[derive(Default)]
+struct AnalyzerState {
+    /// The most recent `fft_size` samples, with `history_pos` pointing at the oldest one.
+    history: Vec<f32>,
+    history_pos: usize,
+    window: Vec<f32>,
+    fft_buffer: Vec<Complex>,
+    /// The FFT's twiddle factors for this FFT size, from [`twiddles()`].
+    twiddles: Vec<Complex>,
+
+    /// The displayed level of every bin, in decibels.
+    levels: Vec<f32>,
+    /// The peak hold level of every bin, in decibels.
+    peaks: Vec<f32>,
+    peak_times: Vec<Instant>,
+
+    sample_rate: f32,
+    min_frequency: f32,
+    max_frequency: f32,
+    last_update: Option<Instant>,
+}
+
+impl AnalyzerState {
+    /// Reallocate the buffers if the FFT size changed.
+    fn resize(&mut self, fft_size: usize, floor_db: f32) {
+        if self.history.len() == fft_size {
+            return;
+        }
+
+        let now = Instant::now();
+        self.history = vec![0.0; fft_size];
+        self.history_pos = 0;
+        self.fft_buffer = vec![Complex::default(); fft_size];
+        self.twiddles = twiddles(fft_size);
+        self.levels = vec![floor_db; fft_size / 2 + 1];
+        self.peaks = vec![floor_db; fft_size / 2 + 1];
+        self.peak_times = vec![now; fft_size / 2 + 1];
+
+        // A Hann window
+        self.window = (0..fft_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / fft_size as f32).cos())
+            .collect();
+    }
+
+    /// Let the displayed levels fall by `decay` decibels per second over `dt` seconds. This runs
+    /// on every frame, whether new samples came in or not, so the spectrum keeps falling once the
+    /// plugin stops sending them.
+    fn decay(&mut self, decay: f32, dt: f32, floor_db: f32) {
+        for level in &mut self.levels {
+            *level = (*level - decay * dt).max(floor_db);
+        }
+    }
+
+    /// Take the windowed FFT of the history and fold the resulting levels into the displayed
+    /// levels.
+    fn analyze(&mut self, floor_db: f32) {
+        let fft_size = self.history.len();
+        for (i, bin) in self.fft_buffer.iter_mut().enumerate() {
+            let sample = self.history[(self.history_pos + i) % fft_size];
+            *bin = Complex {
+                re: sample * self.window[i],
+                im: 0.0,
+            };
+        }
+        fft(&mut self.fft_buffer, &self.twiddles);
+
+        // A full scale sine wave should read 0 dB. The window halves the amplitude, and the energy
+        // is split between the positive and the negative frequencies.
+        let normalization = 4.0 / fft_size as f32;
+        for (level, bin) in self.levels.iter_mut().zip(&self.fft_buffer) {
+            let db = util::gain_to_db(bin.norm() * normalization).max(floor_db);
+            *level = level.max(db);
+        }
+    }
+
+    /// Move the peak hold line up to the displayed levels, and let it fall after it has been held
+    /// for [`PEAK_HOLD_TIME`].
+    fn update_peaks(&mut self, dt: f32, now: Instant) {
+        for ((level, peak), peak_time) in self
+            .levels
+            .iter()
+            .zip(self.peaks.iter_mut())
+            .zip(self.peak_times.iter_mut())
+        {
+            if *level >= *peak {
+                *peak = *level;
+                *peak_time = now;
+            } else if now - *peak_time >= PEAK_HOLD_TIME {
+                *peak = (*peak - PEAK_HOLD_DECAY * dt).max(*level);
+            }
+        }
+    }
+}
+
+/// Drains the ring buffer into the [`AnalyzerState`] and runs the analysis. This doesn't draw
+/// anything itself.
+struct AnalyzerInput<S, R> {
+    samples: S,
+    sample_rate: R,
+    state: Rc<RefCell<AnalyzerState>>,
+}
+
+impl<S, R> View for AnalyzerInput<S, R>
+where
+    S: Lens<Target = RingBufferOutput<f32>>,
+    R: Lens<Target = f32>,
+{
+    fn draw(&self, cx: &mut DrawContext, _canvas: &mut Canvas) {
+        let fft_size = SpectrumAnalyzer::fft_size.get(cx);
+        let decay = SpectrumAnalyzer::decay.get(cx);
+        let sample_rate = self.sample_rate.get(cx);
+        // Anything below the bottom of the display doesn't need to be tracked
+        let floor_db = -240.0;
+
+        let mut state = self.state.borrow_mut();
+        state.resize(fft_size, floor_db);
+        state.sample_rate = sample_rate;
+        state.min_frequency = SpectrumAnalyzer::min_frequency.get(cx);
+        state.max_frequency = SpectrumAnalyzer::max_frequency.get(cx);
+
+        // The levels fall over the time since the last frame, even when no samples came in
+        let now = Instant::now();
+        let dt = state
+            .last_update
+            .map(|last_update| (now - last_update).as_secs_f32())
+            .unwrap_or(0.0);
+        state.last_update = Some(now);
+        state.decay(decay, dt, floor_db);
+
+        let mut received = false;
+        for sample in self.samples.get(cx).drain() {
+            let history_pos = state.history_pos;
+            state.history[history_pos] = sample;
+            state.history_pos = (history_pos + 1) % fft_size;
+            received = true;
+        }
+
+        if received {
+            state.analyze(floor_db);
+        }
+        state.update_peaks(dt, now);
+    }
+}
+
+enum SpectrumLayerKind {
+    /// Lines at round frequencies and levels, drawn with the `color`.
+    Grid,
+    /// The spectrum itself. The area below it is filled with the `background-color`, and the line
+    /// is drawn with the `color` and `border-width`.
+    Spectrum,
+    /// The peak hold line, drawn with the `color` and `border-width`.
+    PeakHold,
+}
+
+struct SpectrumLayer {
+    kind: SpectrumLayerKind,
+    state: Rc<RefCell<AnalyzerState>>,
+    min_db: f32,
+    max_db: f32,
+}
+
+impl SpectrumLayer {
+    /// The outline of the given levels. Bins that share a pixel column are combined by taking the
+    /// loudest one, so high frequencies don't turn into a solid block.
+    fn spectrum_path(
+        &self,
+        state: &AnalyzerState,
+        levels: &[f32],
+        bounds: BoundingBox,
+    ) -> vg::Path {
+        let mut path = vg::Path::new();
+        if state.sample_rate <= 0.0 || levels.len() < 2 {
+            return path;
+        }
+
+        let bin_width = state.sample_rate / state.history.len() as f32;
+        let db_to_y = |db: f32| {
+            let t = ((db - self.min_db) / (self.max_db - self.min_db)).clamp(0.0, 1.0);
+            bounds.y + bounds.h * (1.0 - t)
+        };
+
+        let mut column: Option<(f32, f32)> = None;
+        let mut started = false;
+        let mut point = |path: &mut vg::Path, x: f32, db: f32| {
+            if started {
+                path.line_to(x, db_to_y(db));
+            } else {
+                path.move_to(x, db_to_y(db));
+                started = true;
+            }
+        };
+
+        for (bin, &db) in levels.iter().enumerate().skip(1) {
+            let frequency = bin as f32 * bin_width;
+            let t = frequency_to_t(frequency, state.min_frequency, state.max_frequency);
+            if t < 0.0 {
+                continue;
+            }
+
+            let x = (bounds.x + t * bounds.w).round();
+            match column {
+                Some((column_x, column_db)) if column_x == x => {
+                    column = Some((x, column_db.max(db)));
+                }
+                Some((column_x, column_db)) => {
+                    point(&mut path, column_x, column_db);
+                    column = Some((x, db));
+                }
+                None => column = Some((x, db)),
+            }
+
+            if t > 1.0 {
+                break;
+            }
+        }
+        if let Some((column_x, column_db)) = column {
+            point(&mut path, column_x, column_db);
+        }
+
+        path
+    }
+}
+
+impl View for SpectrumLayer {
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let state = self.state.borrow();
+        let mut line_paint = vg::Paint::color(cx.font_color().into());
+        line_paint.set_line_width(cx.border_width());
+
+        match self.kind {
+            SpectrumLayerKind::Grid => {
+                let mut path = vg::Path::new();
+                for (frequency, _) in frequency_grid(state.min_frequency, state.max_frequency) {
+                    let t = frequency_to_t(frequency, state.min_frequency, state.max_frequency);
+                    let x = (bounds.x + t * bounds.w).round() + 0.5;
+                    path.move_to(x, bounds.y);
+                    path.line_to(x, bounds.y + bounds.h);
+                }
+
+                for db in db_grid(self.min_db, self.max_db) {
+                    let t = (db - self.min_db) / (self.max_db - self.min_db);
+                    let y = (bounds.y + bounds.h * (1.0 - t)).round() + 0.5;
+                    path.move_to(bounds.x, y);
+                    path.line_to(bounds.x + bounds.w, y);
+                }
+
+                line_paint.set_line_width(cx.scale_factor());
+                canvas.stroke_path(&path, &line_paint);
+            }
+            SpectrumLayerKind::Spectrum => {
+                let line = self.spectrum_path(&state, &state.levels, bounds);
+
+                let mut fill = line.clone();
+                fill.line_to(bounds.x + bounds.w, bounds.y + bounds.h);
+                fill.line_to(bounds.x, bounds.y + bounds.h);
+                fill.close();
+                canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+
+                canvas.stroke_path(&line, &line_paint);
+            }
+            SpectrumLayerKind::PeakHold => {
+                let line = self.spectrum_path(&state, &state.peaks, bounds);
+                canvas.stroke_path(&line, &line_paint);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn norm(self) -> f32 {
+        (self.re * self.re + self.im * self.im).sqrt()
+    }
+}
+
+/// The twiddle factors `e^(-2πik/n)` for every `k` below `n / 2`, which [`fft()`] needs for a
+/// buffer of length `n`.
+fn twiddles(n: usize) -> Vec<Complex> {
+    (0..n / 2)
+        .map(|k| {
+            let (sin, cos) = (-2.0 * PI * k as f32 / n as f32).sin_cos();
+            Complex { re: cos, im: sin }
+        })
+        .collect()
+}
+
+/// An in-place iterative radix-2 FFT. The buffer's length needs to be a power of two, and
+/// `twiddles` needs to come from [`twiddles()`] for that length.
+fn fft(buffer: &mut [Complex], twiddles: &[Complex]) {
+    let n = buffer.len();
+    debug_assert!(n.is_power_of_two());
+    debug_assert_eq!(twiddles.len(), n / 2);
+    if n < 2 {
+        return;
+    }
+
+    // Bit reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= n {
+        // The twiddle factors for this size are every `n / size`th one of the full size's
+        let stride = n / size;
+        for start in (0..n).step_by(size) {
+            for k in 0..size / 2 {
+                let Complex { re: cos, im: sin } = twiddles[k * stride];
+                let even = buffer[start + k];
+                let odd = buffer[start + k + size / 2];
+                let twiddled = Complex {
+                    re: odd.re * cos - odd.im * sin,
+                    im: odd.re * sin + odd.im * cos,
+                };
+
+                buffer[start + k] = Complex {
+                    re: even.re + twiddled.re,
+                    im: even.im + twiddled.im,
+                };
+                buffer[start + k + size / 2] = Complex {
+                    re: even.re - twiddled.re,
+                    im: even.im - twiddled.im,
+                };
+            }
+        }
+
+        size *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FFT_SIZE: usize = 1024;
+
+    fn transform(samples: impl Fn(usize) -> f32) -> Vec<Complex> {
+        let mut buffer: Vec<Complex> = (0..FFT_SIZE)
+            .map(|i| Complex {
+                re: samples(i),
+                im: 0.0,
+            })
+            .collect();
+        fft(&mut buffer, &twiddles(FFT_SIZE));
+
+        buffer
+    }
+
+    #[test]
+    fn fft_of_impulse_is_flat() {
+        let bins = transform(|i| if i == 0 { 1.0 } else { 0.0 });
+
+        for bin in bins {
+            assert!((bin.re - 1.0).abs() < 1e-5);
+            assert!(bin.im.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn fft_of_dc_is_in_first_bin() {
+        let bins = transform(|_| 1.0);
+
+        assert!((bins[0].re - FFT_SIZE as f32).abs() < 1e-3);
+        assert!(bins[0].im.abs() < 1e-3);
+        for bin in &bins[1..] {
+            assert!(bin.norm() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn fft_of_cosine_is_in_its_bin() {
+        let bins = transform(|i| (2.0 * PI * 10.0 * i as f32 / FFT_SIZE as f32).cos());
+
+        for (i, bin) in bins.iter().enumerate() {
+            let expected = if i == 10 || i == FFT_SIZE - 10 {
+                FFT_SIZE as f32 / 2.0
+            } else {
+                0.0
+            };
+            assert!((bin.norm() - expected).abs() < 1e-2, "bin {i}");
+        }
+    }
+
+    #[test]
+    fn full_scale_sine_reads_zero_db() {
+        let floor_db = -240.0;
+        let mut state = AnalyzerState::default();
+        state.resize(FFT_SIZE, floor_db);
+
+        let bin = 64;
+        for (i, sample) in state.history.iter_mut().enumerate() {
+            *sample = (2.0 * PI * bin as f32 * i as f32 / FFT_SIZE as f32).sin();
+        }
+        state.analyze(floor_db);
+
+        assert!(state.levels[bin].abs() < 0.1, "{} dB", state.levels[bin]);
+        // Everything but the window's main lobe is far below that
+        assert!(state.levels[bin + 4] < -60.0);
+        assert!(state.levels[bin - 4] < -60.0);
+    }
+}
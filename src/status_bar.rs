@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use nih_plug::prelude::{ParamPtr, Params};
+use nih_plug_vizia::{vizia::prelude::*, widgets::RawParamEvent};
+
+/// Keeps track of the parameter under the mouse, for the [`StatusBar`]. Param widgets report when
+/// they're hovered through their [`ParamMenuTarget`][crate::param_menu::ParamMenuTarget].
+///
+/// The descriptions come from the plugin, by parameter ID. Parameters without one only show their
+/// name and value.
+///
+/// ```ignore
+/// create_vizia_editor(editor_state, ViziaTheming::None, move |cx, gui| {
+///     HoveredParam::new(params.as_ref(), |id| match id {
+///         "gain" => Some("The output level, after the saturation".to_owned()),
+///         _ => None,
+///     })
+///     .build(cx);
+///
+///     StatusBar::new(cx);
+/// });
+/// ```
+#[derive(Lens)]
+pub struct HoveredParam {
+    /// Whether a parameter is hovered.
+    pub active: bool,
+    /// The parameter's full name.
+    pub name: String,
+    /// The parameter's value, formatted with its unit.
+    pub value: String,
+    pub description: String,
+
+    #[lens(ignore)]
+    param: Option<ParamPtr>,
+    /// The parameter reported during the current mouse move.
+    #[lens(ignore)]
+    candidate: Option<ParamPtr>,
+    #[lens(ignore)]
+    param_ids: HashMap<ParamPtr, String>,
+    #[lens(ignore)]
+    descriptions: Box<dyn Fn(&str) -> Option<String>>,
+}
+
+/// Emitted by a param widget whenever the mouse moves over it.
+pub(crate) struct ParamHoverEvent(pub ParamPtr);
+
+/// Emitted after a mouse move has reached every view, to find out whether a param widget reported
+/// it.
+struct HoverCheckEvent;
+
+impl HoveredParam {
+    pub fn new(
+        params: &dyn Params,
+        descriptions: impl Fn(&str) -> Option<String> + 'static,
+    ) -> Self {
+        Self {
+            active: false,
+            name: String::new(),
+            value: String::new(),
+            description: String::new(),
+            param: None,
+            candidate: None,
+            param_ids: params
+                .param_map()
+                .into_iter()
+                .map(|(id, param_ptr, _)| (param_ptr, id))
+                .collect(),
+            descriptions: Box::new(descriptions),
+        }
+    }
+
+    fn set_param(&mut self, param: Option<ParamPtr>) {
+        if param != self.param {
+            self.param = param;
+            self.active = param.is_some();
+            self.name = param
+                .map(|param| unsafe { param.name() }.to_owned())
+                .unwrap_or_default();
+            self.description = param
+                .and_then(|param| self.param_ids.get(&param))
+                .and_then(|id| (self.descriptions)(id))
+                .unwrap_or_default();
+        }
+
+        self.update_value();
+    }
+
+    fn update_value(&mut self) {
+        self.value = self
+            .param
+            .map(|param| unsafe {
+                param.normalized_value_to_string(param.unmodulated_normalized_value(), true)
+            })
+            .unwrap_or_default();
+    }
+}
+
+impl Model for HoveredParam {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        // Mouse moves reach the root after the views under the mouse, so the hovered param
+        // widget's report is already queued by now. The check is queued after it.
+        event.map(|window_event: &WindowEvent, _| {
+            if let WindowEvent::MouseMove(..) = window_event {
+                self.candidate = None;
+                cx.emit(HoverCheckEvent);
+            }
+        });
+
+        event.map(|ParamHoverEvent(param), meta| {
+            self.candidate = Some(*param);
+            meta.consume();
+        });
+
+        event.map(|_: &HoverCheckEvent, meta| {
+            self.set_param(self.candidate);
+            meta.consume();
+        });
+
+        event.map(|param_event: &RawParamEvent, _| {
+            if matches!(
+                param_event,
+                RawParamEvent::EndSetParameter(_) | RawParamEvent::ParametersChanged
+            ) {
+                self.update_value();
+            }
+        });
+    }
+}
+
+/// A bar that shows the full name, value and description of the parameter under the mouse, from a
+/// [`HoveredParam`]. It gets the `active` class while a parameter is hovered.
+pub struct StatusBar;
+
+impl StatusBar {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Label::new(cx, HoveredParam::name).class("name");
+            Label::new(cx, HoveredParam::value).class("value");
+            Label::new(cx, HoveredParam::description).class("description");
+        })
+        .toggle_class("active", HoveredParam::active)
+    }
+}
+
+impl View for StatusBar {
+    fn element(&self) -> Option<&'static str> {
+        Some("statusbar")
+    }
+}
@@ -0,0 +1,145 @@
+//! Loading a stylesheet from disk and reloading it whenever the file changes, for iterating on a
+//! plugin's look while the editor is open.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use nih_plug_vizia::vizia::prelude::*;
+
+use crate::theme;
+
+/// How often the file's modification time is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Recompiles the stylesheet from [`apply_styles_from_file()`][crate::apply_styles_from_file]
+/// when it changes. If it doesn't compile, the previous version stays applied and the error is
+/// stored in [`StyleWatcher::error`], which is what [`StyleErrors`] displays.
+#[derive(Lens)]
+pub struct StyleWatcher {
+    pub error: Option<String>,
+    #[lens(ignore)]
+    path: PathBuf,
+    #[lens(ignore)]
+    stylesheet: SharedStylesheet,
+    #[lens(ignore)]
+    last_modified: Option<SystemTime>,
+}
+
+pub enum StyleWatcherEvent {
+    /// Recompile and re-apply the stylesheet. This is emitted automatically when the file changes.
+    Reload,
+}
+
+/// Emitted every [`POLL_INTERVAL`] to check whether the file changed.
+struct PollEvent;
+
+impl StyleWatcher {
+    /// Compile the file at `path`, add it as a stylesheet, and start watching it. When the file
+    /// doesn't compile, ASTRA's default stylesheet is used until it does.
+    pub(crate) fn watch(cx: &mut Context, path: &Path) -> Result<(), std::io::Error> {
+        let (css, error) = match theme::compile_file(path) {
+            Ok(css) => (css, None),
+            Err(err) => (
+                String::from(grass::include!("static/styles.scss")),
+                Some(err.to_string()),
+            ),
+        };
+
+        let stylesheet = SharedStylesheet(Arc::new(Mutex::new(css)));
+        cx.add_stylesheet(stylesheet.clone())?;
+
+        Self {
+            error,
+            path: path.to_owned(),
+            stylesheet,
+            last_modified: modified(path),
+        }
+        .build(cx);
+
+        cx.spawn(|cx| loop {
+            thread::sleep(POLL_INTERVAL);
+
+            // This fails once the editor has been closed
+            if cx.emit(PollEvent).is_err() {
+                break;
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl Model for StyleWatcher {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|watcher_event, meta| match watcher_event {
+            StyleWatcherEvent::Reload => {
+                match theme::compile_file(&self.path) {
+                    Ok(css) => {
+                        *self.stylesheet.0.lock().unwrap() = css;
+
+                        self.error = match cx.reload_styles() {
+                            Ok(()) => None,
+                            Err(err) => Some(err.to_string()),
+                        };
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                meta.consume();
+            }
+        });
+
+        event.map(|PollEvent, meta| {
+            let current = modified(&self.path);
+            if current != self.last_modified {
+                self.last_modified = current;
+                cx.emit(StyleWatcherEvent::Reload);
+            }
+
+            meta.consume();
+        });
+    }
+}
+
+/// The file's modification time, if it can be read.
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// The compiled stylesheet, which gets swapped out when the file changes. Vizia reads it again
+/// when the styles are reloaded.
+#[derive(Clone)]
+struct SharedStylesheet(Arc<Mutex<String>>);
+
+impl IntoCssStr for SharedStylesheet {
+    fn get_style(&self) -> Result<String, std::io::Error> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+}
+
+/// Shows the [`StyleWatcher`]'s compile errors, and hides itself while there aren't any. This only
+/// works in editors that use [`apply_styles_from_file()`][crate::apply_styles_from_file].
+pub struct StyleErrors;
+
+impl StyleErrors {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Label::new(
+                cx,
+                StyleWatcher::error.map(|error| error.clone().unwrap_or_default()),
+            )
+            .class("message");
+        })
+        .display(StyleWatcher::error.map(|error| error.is_some()))
+    }
+}
+
+impl View for StyleErrors {
+    fn element(&self) -> Option<&'static str> {
+        Some("styleerrors")
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use nih_plug_vizia::vizia::prelude::*;
+
+/// Whether views are styled with the light gray scale, or with the inverted one from
+/// `$grays-dark`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data, Default)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Holds the current [`ColorScheme`]. The editor builds this once, at the root, so the color scheme
+/// can be changed from anywhere with a [`ThemeEvent`]. It only takes effect on views that have been
+/// bound to it with [`ColorSchemeModifiers::color_scheme()`], usually the editor's outermost view.
+///
+/// ```ignore
+/// Theme::default().build(cx);
+///
+/// VStack::new(cx, |cx| {
+///     Button::new(
+///         cx,
+///         |cx| cx.emit(ThemeEvent::ToggleColorScheme),
+///         |cx| Label::new(cx, "DARK MODE"),
+///     );
+/// })
+/// .color_scheme(Theme::color_scheme);
+/// ```
+#[derive(Lens, Default)]
+pub struct Theme {
+    pub color_scheme: ColorScheme,
+}
+
+pub enum ThemeEvent {
+    SetColorScheme(ColorScheme),
+    ToggleColorScheme,
+}
+
+impl Model for Theme {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|theme_event, meta| {
+            match theme_event {
+                ThemeEvent::SetColorScheme(color_scheme) => self.color_scheme = *color_scheme,
+                ThemeEvent::ToggleColorScheme => {
+                    self.color_scheme = match self.color_scheme {
+                        ColorScheme::Light => ColorScheme::Dark,
+                        ColorScheme::Dark => ColorScheme::Light,
+                    }
+                }
+            }
+
+            meta.consume();
+        });
+    }
+}
+
+pub trait ColorSchemeModifiers {
+    /// Style this view and everything inside of it with the given color scheme, which is usually
+    /// [`Theme::color_scheme`].
+    fn color_scheme(self, color_scheme: impl Lens<Target = ColorScheme>) -> Self;
+}
+
+impl<V: View> ColorSchemeModifiers for Handle<'_, V> {
+    fn color_scheme(self, color_scheme: impl Lens<Target = ColorScheme>) -> Self {
+        self.toggle_class(
+            "dark",
+            color_scheme.map(|color_scheme| *color_scheme == ColorScheme::Dark),
+        )
+    }
+}
+
+/// The files [`ThemeTokens::stylesheet()`] compiles the stylesheet from, as they would be
+/// imported from `static/`.
+const SCSS_SOURCES: [(&str, &str); 3] = [
+    ("theme.scss", include_str!("../static/theme.scss")),
+    ("utils.scss", include_str!("../static/utils.scss")),
+    ("styles.scss", include_str!("../static/styles.scss")),
+];
+
+/// Values for the variables in ASTRA's `theme.scss`, used to generate a stylesheet for plugins that
+/// don't use ASTRA's default look. Anything that isn't set keeps its default value. The resulting
+/// stylesheet can be applied with [`apply_themed_styles()`][crate::apply_themed_styles].
+///
+/// ```ignore
+/// let tokens = ThemeTokens::new()
+///     .component_size(16.0)
+///     .color("red", Color::rgb(230, 40, 60))
+///     .overrides(r#"$font-sizes: ("sm": 9, "md": 11, "lg": 13)"#);
+///
+/// apply_themed_styles(cx, &tokens)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ThemeTokens {
+    /// SCSS variables without the leading `$`, and the expressions they're set to.
+    variables: Vec<(String, String)>,
+    /// Entries for `$color-overrides`.
+    colors: Vec<(String, String)>,
+    /// Extra `$variable: value` pairs, separated by commas.
+    overrides: Vec<String>,
+}
+
+impl ThemeTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The font family used by every view. The font itself needs to be registered separately,
+    /// using `cx.add_font_mem()`. Defaults to Voidstar Mono.
+    pub fn font(self, font: &str) -> Self {
+        self.variable("font", format!("{font:?}"))
+    }
+
+    /// The height of buttons, sliders, and most other components, in logical pixels. Defaults to
+    /// 14.
+    pub fn component_size(self, size: f32) -> Self {
+        self.variable("component-size", format!("{size}px"))
+    }
+
+    /// Replace one of the shades of the light gray scale, from 50 through 950.
+    pub fn gray(self, shade: u16, color: Color) -> Self {
+        self.variable(format!("gray-{shade}"), scss_color(color))
+    }
+
+    /// Replace one of the shades of the dark gray scale, from 50 (the darkest) through 950 (the
+    /// lightest).
+    pub fn dark_gray(self, shade: u16, color: Color) -> Self {
+        self.variable(format!("gray-dark-{shade}"), scss_color(color))
+    }
+
+    /// Add or replace one of the accent colors in `$colors`, like `"red"` or `"blue"`.
+    pub fn color(mut self, name: &str, color: Color) -> Self {
+        self.colors.retain(|(other, _)| other != name);
+        self.colors.push((name.to_owned(), scss_color(color)));
+        self
+    }
+
+    /// Set variables from `theme.scss` with plain SCSS, like
+    /// `$component-size: 16px, $font: "Inter"`. A variable can't be set both here and through one
+    /// of the other methods.
+    pub fn overrides(mut self, overrides: impl Into<String>) -> Self {
+        self.overrides.push(overrides.into());
+        self
+    }
+
+    /// Compile ASTRA's stylesheet with these tokens.
+    pub fn stylesheet(&self) -> Result<String, Box<grass::Error>> {
+        grass::from_string(
+            self.entry_point(),
+            &grass::Options::default().fs(&SourcesFs),
+        )
+    }
+
+    fn variable(mut self, name: impl Into<String>, value: String) -> Self {
+        let name = name.into();
+        self.variables.retain(|(other, _)| *other != name);
+        self.variables.push((name, value));
+        self
+    }
+
+    /// `styles.scss` with `theme.scss` configured by the tokens.
+    fn entry_point(&self) -> String {
+        let mut configuration: Vec<String> = self
+            .variables
+            .iter()
+            .map(|(name, value)| format!("${name}: {value}"))
+            .collect();
+        if !self.colors.is_empty() {
+            let colors: Vec<String> = self
+                .colors
+                .iter()
+                .map(|(name, value)| format!("{name:?}: {value}"))
+                .collect();
+            configuration.push(format!("$color-overrides: ({})", colors.join(", ")));
+        }
+        configuration.extend(self.overrides.iter().cloned());
+
+        if configuration.is_empty() {
+            String::from("@use \"styles\";\n")
+        } else {
+            format!(
+                "@use \"theme\" with ({});\n@use \"styles\";\n",
+                configuration.join(", ")
+            )
+        }
+    }
+}
+
+fn scss_color(color: Color) -> String {
+    format!(
+        "rgba({}, {}, {}, {})",
+        color.r(),
+        color.g(),
+        color.b(),
+        color.a() as f32 / 255.0
+    )
+}
+
+/// Compile a stylesheet from a file on disk. The file can `@use "theme"` and `@use "styles"` to
+/// build on ASTRA's stylesheet, and it can import other files relative to its own directory.
+pub(crate) fn compile_file(path: &Path) -> Result<String, Box<grass::Error>> {
+    let source = std::fs::read_to_string(path)?;
+
+    let mut options = grass::Options::default().fs(&SourcesFs);
+    if let Some(directory) = path.parent() {
+        options = options.load_path(directory);
+    }
+
+    grass::from_string(source, &options)
+}
+
+/// Serves [`SCSS_SOURCES`] to grass, so the stylesheet can be compiled at runtime without the
+/// sources being on disk. Any other files are read from disk.
+#[derive(Debug)]
+struct SourcesFs;
+
+impl SourcesFs {
+    fn source(&self, path: &Path) -> Option<&'static str> {
+        SCSS_SOURCES
+            .iter()
+            .find(|(name, _)| Path::new(name) == path)
+            .map(|(_, source)| *source)
+    }
+}
+
+impl grass::Fs for SourcesFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        grass::StdFs.is_dir(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.source(path).is_some() || grass::StdFs.is_file(path)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        match self.source(path) {
+            Some(source) => Ok(source.as_bytes().to_vec()),
+            None => grass::StdFs.read(path),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use nih_plug::prelude::*;
+
+use crate::param_slider::SliderTick;
+
+/// Ranges spanning more than this ratio that are skewed get logarithmically spaced ticks at
+/// 1, 2, 5, ... times a power of ten, instead of linearly spaced ones.
+const LOGARITHMIC_RATIO: f64 = 20.0;
+
+/// The minimum normalized distance between two minor ticks, relative to the distance between two
+/// major ticks.
+const MINOR_SPACING_RATIO: f32 = 0.2;
+
+impl SliderTick {
+    /// Ticks at round values within a [`FloatParam`]'s range, placed at the correct normalized
+    /// positions for skewed and reversed ranges. Roughly `major_ticks` of them are labeled using
+    /// the parameter's own formatting, and minor (`short`) ticks are placed between those.
+    pub fn from_float_param(param: &FloatParam, major_ticks: usize) -> Vec<SliderTick> {
+        generate_ticks(
+            param.preview_plain(0.0) as f64,
+            param.preview_plain(1.0) as f64,
+            false,
+            major_ticks,
+            |value| param.preview_normalized(value as f32),
+            |normalized| param.normalized_value_to_string(normalized, false),
+        )
+    }
+
+    /// The same as [`SliderTick::from_float_param()`], but only ever placing ticks on integers.
+    pub fn from_int_param(param: &IntParam, major_ticks: usize) -> Vec<SliderTick> {
+        generate_ticks(
+            param.preview_plain(0.0) as f64,
+            param.preview_plain(1.0) as f64,
+            true,
+            major_ticks,
+            |value| param.preview_normalized(value.round() as i32),
+            |normalized| param.normalized_value_to_string(normalized, false),
+        )
+    }
+}
+
+/// A candidate tick value, with a higher `rank` meaning that it's a rounder number. Candidates with
+/// a rank of zero only ever become minor ticks.
+struct Candidate {
+    value: f64,
+    rank: u8,
+}
+
+fn generate_ticks(
+    start: f64,
+    end: f64,
+    integer: bool,
+    major_ticks: usize,
+    to_normalized: impl Fn(f64) -> f32,
+    to_string: impl Fn(f32) -> String,
+) -> Vec<SliderTick> {
+    let (min, max) = (start.min(end), start.max(end));
+    if max <= min || major_ticks == 0 {
+        return Vec::new();
+    }
+
+    // A skewed range maps its geometric mean closer to the center than its arithmetic mean
+    let logarithmic = min > 0.0 && max / min >= LOGARITHMIC_RATIO && {
+        let arithmetic_distance = (to_normalized((min + max) / 2.0) - 0.5).abs();
+        let geometric_distance = (to_normalized((min * max).sqrt()) - 0.5).abs();
+        geometric_distance < arithmetic_distance
+    };
+
+    let candidates = if logarithmic {
+        logarithmic_candidates(min, max)
+    } else {
+        linear_candidates(min, max, integer, major_ticks)
+    };
+
+    // Go through the candidates from roundest to least round, and only keep the ones that are far
+    // enough away from the ones that have already been placed. This keeps skewed ranges from
+    // crowding one end of the slider.
+    let major_spacing = 1.0 / (major_ticks as f32 + 1.0);
+    let minor_spacing = major_spacing * MINOR_SPACING_RATIO;
+
+    let mut candidates: Vec<(f32, &Candidate)> = candidates
+        .iter()
+        .map(|candidate| (to_normalized(candidate.value), candidate))
+        .filter(|(pos, _)| (0.0..=1.0).contains(pos))
+        .collect();
+    candidates.sort_by(|(a_pos, a), (b_pos, b)| b.rank.cmp(&a.rank).then(a_pos.total_cmp(b_pos)));
+
+    let mut placed: Vec<(f32, bool)> = Vec::new();
+    for (pos, candidate) in candidates {
+        let too_close = |spacing: f32| {
+            placed
+                .iter()
+                .any(|(other_pos, _)| (other_pos - pos).abs() < spacing)
+        };
+
+        let major_count = placed.iter().filter(|(_, major)| *major).count();
+        if candidate.rank > 0 && major_count < major_ticks && !too_close(major_spacing) {
+            placed.push((pos, true));
+        } else if !too_close(minor_spacing) {
+            placed.push((pos, false));
+        }
+    }
+
+    placed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    placed
+        .into_iter()
+        .map(|(pos, major)| SliderTick {
+            pos,
+            label: major.then(|| to_string(pos)),
+            short: !major,
+        })
+        .collect()
+}
+
+/// Multiples of a round step size. Multiples of the major step size are ranked higher than the
+/// minor steps in between them.
+fn linear_candidates(min: f64, max: f64, integer: bool, major_ticks: usize) -> Vec<Candidate> {
+    let mut major_step = nice_step((max - min) / major_ticks as f64);
+    if integer {
+        major_step = major_step.max(1.0);
+    }
+
+    // 1 is split into fifths, 2 into halves, and 5 into fifths again
+    let mantissa = major_step / 10f64.powf(major_step.log10().floor());
+    let subdivisions = if (mantissa - 2.0).abs() < 1e-6 { 4 } else { 5 };
+    let mut minor_step = major_step / subdivisions as f64;
+    if integer && minor_step.fract() != 0.0 {
+        minor_step = major_step;
+    }
+
+    let first = (min / minor_step).ceil() as i64;
+    let last = (max / minor_step).floor() as i64;
+
+    (first..=last)
+        .map(|i| {
+            let value = i as f64 * minor_step;
+            let is_major = (value / major_step - (value / major_step).round()).abs() < 1e-6;
+
+            Candidate {
+                value,
+                rank: is_major as u8,
+            }
+        })
+        .collect()
+}
+
+/// 1, 2, 5, and the other multiples of every power of ten within a positive range.
+fn logarithmic_candidates(min: f64, max: f64) -> Vec<Candidate> {
+    let first_decade = min.log10().floor() as i32;
+    let last_decade = max.log10().ceil() as i32;
+
+    (first_decade..=last_decade)
+        .flat_map(|decade| {
+            let power = 10f64.powi(decade);
+            (1..10).map(move |multiple| Candidate {
+                value: multiple as f64 * power,
+                rank: match multiple {
+                    1 => 2,
+                    2 | 5 => 1,
+                    _ => 0,
+                },
+            })
+        })
+        .filter(|candidate| (min..=max).contains(&candidate.value))
+        .collect()
+}
+
+/// The closest 1, 2 or 5 times a power of ten that's at least as large as `step`.
+fn nice_step(step: f64) -> f64 {
+    let power = 10f64.powf(step.log10().floor());
+    let mantissa = step / power;
+
+    let nice_mantissa = if mantissa <= 1.0 {
+        1.0
+    } else if mantissa <= 2.0 {
+        2.0
+    } else if mantissa <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+
+    nice_mantissa * power
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(ticks: &[SliderTick]) -> Vec<&str> {
+        ticks
+            .iter()
+            .filter_map(|tick| tick.label.as_deref())
+            .collect()
+    }
+
+    fn assert_valid(ticks: &[SliderTick], major_ticks: usize) {
+        assert!(ticks.windows(2).all(|pair| pair[0].pos < pair[1].pos));
+        assert!(ticks.iter().all(|tick| (0.0..=1.0).contains(&tick.pos)));
+        assert!(ticks.iter().all(|tick| tick.short == tick.label.is_none()));
+        assert!(labels(ticks).len() <= major_ticks);
+    }
+
+    #[test]
+    fn linear_range() {
+        let param = FloatParam::new(
+            "Mix",
+            0.0,
+            FloatRange::Linear {
+                min: 0.0,
+                max: 100.0,
+            },
+        );
+        let ticks = SliderTick::from_float_param(&param, 4);
+
+        assert_valid(&ticks, 4);
+        assert_eq!(labels(&ticks), ["0", "50", "100"]);
+        assert!(ticks.iter().any(|tick| tick.short));
+    }
+
+    #[test]
+    fn skewed_range_is_logarithmic() {
+        let param = FloatParam::new(
+            "Frequency",
+            1000.0,
+            FloatRange::Skewed {
+                min: 20.0,
+                max: 20000.0,
+                factor: FloatRange::skew_factor(-2.0),
+            },
+        );
+        let ticks = SliderTick::from_float_param(&param, 4);
+
+        assert_valid(&ticks, 4);
+        for value in [100.0, 1000.0, 10000.0] {
+            let pos = param.preview_normalized(value);
+            assert!(ticks
+                .iter()
+                .any(|tick| tick.label.is_some() && (tick.pos - pos).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn reversed_range() {
+        static RANGE: FloatRange = FloatRange::Linear {
+            min: 0.0,
+            max: 10.0,
+        };
+        let param = FloatParam::new("Depth", 0.0, FloatRange::Reversed(&RANGE));
+        let ticks = SliderTick::from_float_param(&param, 3);
+
+        assert_valid(&ticks, 3);
+        assert_eq!(labels(&ticks), ["10", "5", "0"]);
+    }
+
+    #[test]
+    fn narrow_range() {
+        let param = FloatParam::new(
+            "Fine",
+            0.32,
+            FloatRange::Linear {
+                min: 0.31,
+                max: 0.33,
+            },
+        );
+        let ticks = SliderTick::from_float_param(&param, 4);
+
+        assert_valid(&ticks, 4);
+        assert!(!labels(&ticks).is_empty());
+    }
+
+    #[test]
+    fn empty_range() {
+        let float_param = FloatParam::new("Fixed", 1.0, FloatRange::Linear { min: 1.0, max: 1.0 });
+        assert!(SliderTick::from_float_param(&float_param, 4).is_empty());
+
+        let int_param = IntParam::new("Fixed", 3, IntRange::Linear { min: 3, max: 3 });
+        assert!(SliderTick::from_int_param(&int_param, 4).is_empty());
+    }
+
+    #[test]
+    fn int_range_with_fewer_values_than_ticks() {
+        let param = IntParam::new("Voices", 1, IntRange::Linear { min: 0, max: 2 });
+        let ticks = SliderTick::from_int_param(&param, 8);
+
+        assert_valid(&ticks, 8);
+        assert_eq!(labels(&ticks), ["0", "1", "2"]);
+        assert_eq!(ticks.len(), 3);
+    }
+
+    #[test]
+    fn int_range_only_has_integer_ticks() {
+        let param = IntParam::new("Semitones", 0, IntRange::Linear { min: -7, max: 7 });
+        let ticks = SliderTick::from_int_param(&param, 4);
+
+        assert_valid(&ticks, 4);
+        for tick in &ticks {
+            let value = param.preview_plain(tick.pos);
+            assert!((param.preview_normalized(value) - tick.pos).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use nih_plug_vizia::vizia::prelude::*;
+
+/// How long the mouse has to rest on a view before its tooltip shows up.
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+
+/// How often the [`TooltipState`] checks whether the delay has passed.
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The tooltip's distance from the cursor, in logical pixels.
+const CURSOR_OFFSET: f32 = 12.0;
+
+/// The state of the [`Tooltip`], which is built at the root along with it.
+#[derive(Lens)]
+pub struct TooltipState {
+    pub text: String,
+    pub visible: bool,
+    /// Where the tooltip is shown, in logical pixels from the top left of the window.
+    pub x: f32,
+    pub y: f32,
+
+    /// When the tooltip for the hovered view should show up, or `None` if no view is waiting for
+    /// its tooltip.
+    #[lens(ignore)]
+    reveal_at: Option<Instant>,
+}
+
+pub enum TooltipEvent {
+    /// Show a tooltip with this text after a delay, unless it's hidden before then. Views with a
+    /// [`TooltipModifiers::tooltip()`] emit this when they're hovered.
+    Show(String),
+    Hide,
+}
+
+/// Emitted every [`TICK_INTERVAL`] to show the tooltip once its delay has passed.
+struct TickEvent;
+
+impl Model for TooltipState {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|tooltip_event, meta| {
+            self.visible = false;
+            self.reveal_at = None;
+
+            if let TooltipEvent::Show(text) = tooltip_event {
+                self.text = text.clone();
+                self.reveal_at = Some(Instant::now() + TOOLTIP_DELAY);
+            }
+
+            meta.consume();
+        });
+
+        event.map(|_: &TickEvent, meta| {
+            if self
+                .reveal_at
+                .is_some_and(|reveal_at| Instant::now() >= reveal_at)
+            {
+                self.reveal_at = None;
+                self.visible = true;
+                self.x = cx.mouse().cursorx / cx.scale_factor() + CURSOR_OFFSET;
+                self.y = cx.mouse().cursory / cx.scale_factor() + CURSOR_OFFSET;
+            }
+
+            meta.consume();
+        });
+
+        // Clicking a view is usually the end of wanting to know what it does
+        event.map(|window_event: &WindowEvent, _| {
+            if let WindowEvent::MouseDown(_) = window_event {
+                self.visible = false;
+                self.reveal_at = None;
+            }
+        });
+    }
+}
+
+/// Shows the tooltips of views with a [`TooltipModifiers::tooltip()`] next to the cursor. It's
+/// shared by every view, so it's built once at the root of the editor, after everything else.
+///
+/// ```ignore
+/// Button::new(cx, |cx| cx.emit(AppEvent::Reset), |cx| Label::new(cx, "RESET"))
+///     .tooltip("Set every parameter to its default value");
+///
+/// Tooltip::new(cx);
+/// ```
+pub struct Tooltip;
+
+impl Tooltip {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        TooltipState {
+            text: String::new(),
+            visible: false,
+            x: 0.0,
+            y: 0.0,
+            reveal_at: None,
+        }
+        .build(cx);
+
+        cx.spawn(|cx| loop {
+            thread::sleep(TICK_INTERVAL);
+
+            // This fails once the editor has been closed
+            if cx.emit(TickEvent).is_err() {
+                break;
+            }
+        });
+
+        Self.build(cx, |cx| {
+            Label::new(cx, TooltipState::text).class("text");
+        })
+        .display(TooltipState::visible)
+        .left(TooltipState::x.map(|x| Pixels(*x)))
+        .top(TooltipState::y.map(|y| Pixels(*y)))
+        .hoverable(false)
+    }
+}
+
+impl View for Tooltip {
+    fn element(&self) -> Option<&'static str> {
+        Some("tooltip")
+    }
+}
+
+pub trait TooltipModifiers {
+    /// Show `text` in the [`Tooltip`] when the mouse rests on this view. This uses the view's
+    /// `on_hover()` and `on_hover_out()` actions.
+    fn tooltip(self, text: impl Into<String>) -> Self;
+}
+
+impl<V: View> TooltipModifiers for Handle<'_, V> {
+    fn tooltip(self, text: impl Into<String>) -> Self {
+        let text = text.into();
+
+        self.on_hover(move |cx| cx.emit(TooltipEvent::Show(text.clone())))
+            .on_hover_out(|cx| cx.emit(TooltipEvent::Hide))
+    }
+}
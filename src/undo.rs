@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use nih_plug::prelude::{GuiContext, ParamPtr, Params};
+use nih_plug_vizia::{vizia::prelude::*, widgets::RawParamEvent};
+
+/// The most edits that are kept. Older ones are dropped.
+const MAX_EDITS: usize = 256;
+
+/// Gestures that start and end this quickly were made with the scroll wheel or the arrow keys.
+const INSTANT_GESTURE: Duration = Duration::from_millis(50);
+
+/// Instant gestures on the same parameter are merged into one edit if they're this close together.
+const COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// One completed gesture on a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamEdit {
+    pub param_id: String,
+    pub old_value: f32,
+    pub new_value: f32,
+    param: ParamPtr,
+    /// When the gesture ended, or `None` if it wasn't an instant gesture and can't be merged.
+    instant_end: Option<Instant>,
+}
+
+impl Data for ParamEdit {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// A gesture that has begun but not ended yet.
+struct Gesture {
+    old_value: f32,
+    new_value: Option<f32>,
+    start: Instant,
+}
+
+/// Records every parameter gesture made in the editor, so it can be undone and redone. Gestures
+/// are tracked through the `begin_set_parameter()`/`end_set_parameter()` calls every ASTRA widget
+/// makes, and each one becomes a single [`ParamEdit`] with the parameter's normalized value before
+/// and after. Bursts of scrolling or arrow key presses on the same parameter are merged into one
+/// edit.
+///
+/// Once built, [`UndoEvent`]s and Ctrl+Z / Ctrl+Shift+Z (Cmd on macOS) undo and redo edits while
+/// nothing else handles those keys. [`UndoButtons`] adds buttons for them.
+///
+/// ```ignore
+/// create_vizia_editor(editor_state, ViziaTheming::None, move |cx, gui| {
+///     UndoHistory::new(gui, params.as_ref()).build(cx);
+/// });
+/// ```
+#[derive(Lens)]
+pub struct UndoHistory {
+    /// The edits that can be undone, with the most recent one last.
+    pub undo_stack: Vec<ParamEdit>,
+    /// The edits that were undone and can be redone, with the most recently undone one last.
+    pub redo_stack: Vec<ParamEdit>,
+
+    #[lens(ignore)]
+    gui: Arc<dyn GuiContext>,
+    #[lens(ignore)]
+    param_ids: HashMap<ParamPtr, String>,
+    #[lens(ignore)]
+    gestures: HashMap<ParamPtr, Gesture>,
+}
+
+pub enum UndoEvent {
+    Undo,
+    Redo,
+    /// Forget every edit, for example after loading a preset.
+    Clear,
+}
+
+impl UndoHistory {
+    pub fn new(gui: Arc<dyn GuiContext>, params: &dyn Params) -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            gui,
+            param_ids: params
+                .param_map()
+                .into_iter()
+                .map(|(id, param_ptr, _)| (param_ptr, id))
+                .collect(),
+            gestures: HashMap::new(),
+        }
+    }
+
+    fn begin(&mut self, param: ParamPtr) {
+        self.gestures.entry(param).or_insert_with(|| Gesture {
+            old_value: unsafe { param.unmodulated_normalized_value() },
+            new_value: None,
+            start: Instant::now(),
+        });
+    }
+
+    fn end(&mut self, param: ParamPtr) {
+        let Some(gesture) = self.gestures.remove(&param) else {
+            return;
+        };
+        let Some(new_value) = gesture.new_value else {
+            return;
+        };
+        let Some(param_id) = self.param_ids.get(&param).cloned() else {
+            return;
+        };
+
+        let now = Instant::now();
+        let instant_end = (now - gesture.start < INSTANT_GESTURE).then_some(now);
+
+        // Merge instant gestures into the previous edit if that was an instant gesture on the
+        // same parameter as well
+        if let Some(last) = self.undo_stack.last_mut() {
+            let coalesce = last.param == param
+                && instant_end.is_some()
+                && last
+                    .instant_end
+                    .is_some_and(|end| now - end < COALESCE_WINDOW);
+            if coalesce {
+                last.new_value = new_value;
+                last.instant_end = instant_end;
+                if last.new_value == last.old_value {
+                    self.undo_stack.pop();
+                }
+
+                self.redo_stack.clear();
+                return;
+            }
+        }
+
+        if new_value == gesture.old_value {
+            return;
+        }
+
+        self.undo_stack.push(ParamEdit {
+            param_id,
+            old_value: gesture.old_value,
+            new_value,
+            param,
+            instant_end,
+        });
+        if self.undo_stack.len() > MAX_EDITS {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Set a parameter as a gesture of its own. This goes to the [`GuiContext`] directly instead
+    /// of emitting [`RawParamEvent`]s, so it isn't recorded as a new edit.
+    fn set(&self, param: ParamPtr, value: f32) {
+        unsafe {
+            self.gui.raw_begin_set_parameter(param);
+            self.gui.raw_set_parameter_normalized(param, value);
+            self.gui.raw_end_set_parameter(param);
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(mut edit) = self.undo_stack.pop() {
+            self.set(edit.param, edit.old_value);
+
+            // An undone edit never gets merged with a new one
+            edit.instant_end = None;
+            self.redo_stack.push(edit);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(edit) = self.redo_stack.pop() {
+            self.set(edit.param, edit.new_value);
+            self.undo_stack.push(edit);
+        }
+    }
+}
+
+impl Model for UndoHistory {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|undo_event, meta| {
+            match undo_event {
+                UndoEvent::Undo => self.undo(),
+                UndoEvent::Redo => self.redo(),
+                UndoEvent::Clear => {
+                    self.undo_stack.clear();
+                    self.redo_stack.clear();
+                }
+            }
+
+            meta.consume();
+        });
+
+        // The widgets' events reach the root after they've been sent, so this only watches them.
+        // The new value is taken from the events rather than the parameter, which may not have
+        // been updated yet.
+        event.map(|param_event: &RawParamEvent, _| match param_event {
+            RawParamEvent::BeginSetParameter(param) => self.begin(*param),
+            RawParamEvent::SetParameterNormalized(param, value) => {
+                if let Some(gesture) = self.gestures.get_mut(param) {
+                    gesture.new_value = Some(*value);
+                }
+            }
+            RawParamEvent::ResetParameter(param) => {
+                if let Some(gesture) = self.gestures.get_mut(param) {
+                    gesture.new_value = Some(unsafe { param.default_normalized_value() });
+                }
+            }
+            RawParamEvent::EndSetParameter(param) => self.end(*param),
+            RawParamEvent::ParametersChanged => {}
+        });
+
+        event.map(|window_event: &WindowEvent, meta| {
+            if matches!(window_event, WindowEvent::KeyDown(Code::KeyZ, _))
+                && cx.modifiers().command()
+            {
+                if cx.modifiers().shift() {
+                    self.redo();
+                } else {
+                    self.undo();
+                }
+
+                meta.consume();
+            }
+        });
+    }
+}
+
+/// Undo and redo buttons for an [`UndoHistory`], which get the `disabled` class while there's
+/// nothing to undo or redo.
+pub struct UndoButtons;
+
+impl UndoButtons {
+    pub fn new(cx: &mut Context) -> Handle<Self> {
+        Self.build(cx, |cx| {
+            Button::new(
+                cx,
+                |cx| cx.emit(UndoEvent::Undo),
+                |cx| Label::new(cx, "UNDO"),
+            )
+            .class("undo")
+            .toggle_class("disabled", UndoHistory::undo_stack.map(Vec::is_empty));
+            Button::new(
+                cx,
+                |cx| cx.emit(UndoEvent::Redo),
+                |cx| Label::new(cx, "REDO"),
+            )
+            .class("redo")
+            .toggle_class("disabled", UndoHistory::redo_stack.map(Vec::is_empty));
+        })
+    }
+}
+
+impl View for UndoButtons {
+    fn element(&self) -> Option<&'static str> {
+        Some("undobuttons")
+    }
+}